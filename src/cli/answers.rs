@@ -0,0 +1,111 @@
+//! `--answers` and `--save-answers`: turning an interactive session into a
+//! command that can be run again.
+//!
+//! Values typed at prompts are recorded from [`Event::VariableAnswered`] events
+//! and written as a flat TOML file, which `--answers` reads back like a
+//! `--vars-file`. Secrets are never written; their names are kept so the user
+//! can be told to pass them another way.
+
+use spark::events::{Event, Reporter};
+use spark::Error;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    sync::{Arc, Mutex},
+};
+
+/// Answers given so far, shared with the reporter that records them.
+#[derive(Clone, Default)]
+pub struct Answers(Arc<Mutex<Recorded>>);
+
+#[derive(Default)]
+struct Recorded {
+    values: BTreeMap<String, String>,
+    /// Names of secrets that were answered, whose values are not recorded.
+    secrets: BTreeSet<String>,
+}
+
+impl Answers {
+    /// Starts from the answers replayed with `--answers`, so saving again keeps them.
+    pub fn new(replayed: &[(String, String)]) -> Self {
+        Self(Arc::new(Mutex::new(Recorded {
+            values: replayed.iter().cloned().collect(),
+            secrets: BTreeSet::new(),
+        })))
+    }
+
+    /// Reporter that records answers and passes every event on to `reporter`.
+    pub fn recorder(&self, reporter: Arc<dyn Reporter>) -> impl Reporter + 'static {
+        let answers = self.0.clone();
+        move |event: &Event| {
+            if let Event::VariableAnswered { name, value } = event {
+                let mut answers = answers.lock().unwrap();
+                match value {
+                    Some(value) => {
+                        answers.values.insert(name.clone(), value.clone());
+                    }
+                    None => {
+                        answers.secrets.insert(name.clone());
+                    }
+                }
+            }
+            reporter.report(event);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().values.is_empty()
+    }
+
+    /// Secrets that were answered but will not be saved.
+    pub fn secrets(&self) -> Vec<String> {
+        self.0.lock().unwrap().secrets.iter().cloned().collect()
+    }
+
+    /// Writes the answers to `path` as `NAME = "value"` lines.
+    pub fn save(&self, path: &str) -> spark::Result<()> {
+        let answers = self.0.lock().unwrap();
+        let content = format!(
+            "# Replay with: spark <template> --answers {}\n{}",
+            path,
+            toml::to_string(&answers.values).map_err(Error::from)?
+        );
+        fs::write(path, content).map_err(|e| Error::OutputWrite {
+            path: path.to_string(),
+            message: e.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spark::events::SilentReporter;
+
+    #[test]
+    fn records_answers_but_not_secrets() {
+        let answers = Answers::new(&[("REGION".to_string(), "eu".to_string())]);
+        let recorder = answers.recorder(Arc::new(SilentReporter));
+        for (name, value) in [("NAME", Some("billing")), ("API_TOKEN", None)] {
+            recorder.report(&Event::VariableAnswered {
+                name: name.to_string(),
+                value: value.map(str::to_string),
+            });
+        }
+
+        assert_eq!(answers.secrets(), ["API_TOKEN"]);
+
+        let path = std::env::temp_dir().join("spark_test_answers.toml");
+        let path = path.to_string_lossy();
+        answers.save(&path).unwrap();
+        let saved = crate::vars::load_vars_file(&path).unwrap();
+        assert_eq!(
+            saved,
+            [
+                ("NAME".to_string(), "billing".to_string()),
+                ("REGION".to_string(), "eu".to_string())
+            ]
+        );
+        let _ = fs::remove_file(path.as_ref());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::vars;
 use clap::{App, Arg, Command};
 
 pub struct Cli;
@@ -20,6 +21,15 @@ impl Cli {
                     .short('q')
                     .requires("template"),
             )
+            .arg(
+                Arg::new("format")
+                    .help("Output format; json prints one event per line and never prompts")
+                    .long("format")
+                    .takes_value(true)
+                    .possible_values(["text", "json"])
+                    .default_value("text")
+                    .requires("template"),
+            )
             .arg(
                 Arg::new("config")
                     .long("config")
@@ -35,6 +45,16 @@ impl Cli {
                     .takes_value(true)
                     .requires("template"),
             )
+            .arg(
+                Arg::new("data")
+                    .help("Read {{$.path}} data from a JSON, YAML, TOML or .env file, - for stdin; repeatable, later files are merged over earlier ones")
+                    .long("data")
+                    .short('d')
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .multiple_occurrences(true)
+                    .requires("template"),
+            )
             .arg(
                 Arg::new("git")
                     .help("Initialize a git repo regardless of template options")
@@ -51,12 +71,279 @@ impl Cli {
             )
             .arg(
                 Arg::new("keywords")
-                .help("Key, value pairs to be replaced,\nYou can use this to skip user inputs and other function calls,\nExample: 'name=spark, author=pwnxpl0it'")
+                .help("Key, value pairs to be replaced,\nYou can use this to skip user inputs and other function calls,\nExample: 'name=spark, author=pwnxpl0it'\nQuote or escape values containing commas: 'motto=\"fast, small\"'")
                 .long("from")
                 .takes_value(true)
+                .validator(|s| spark::vars::parse_from(s).map(|_| ()))
                 .requires("template")
             )
-            .subcommand(Command::new("init").about("Creates a template for the current directory"))
+            .arg(
+                Arg::new("set")
+                    .help("Set one variable, the value is taken as is; repeatable, overrides --from")
+                    .long("set")
+                    .takes_value(true)
+                    .value_name("NAME=VALUE")
+                    .multiple_occurrences(true)
+                    .validator(|s| vars::parse_assignment(s).map(|_| ()))
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("set-json")
+                    .help("Add a JSON value to the {{$.path}} data, e.g. 'db.ports=[5432,5433]'; repeatable")
+                    .long("set-json")
+                    .takes_value(true)
+                    .value_name("PATH=JSON")
+                    .multiple_occurrences(true)
+                    .validator(|s| vars::parse_json_assignment(s).map(|_| ()))
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("vars-file")
+                    .help("Read variables from a JSON, YAML, TOML or .env file; --from and --set override them")
+                    .long("vars-file")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("answers")
+                    .help("Replay answers saved with --save-answers instead of being asked again")
+                    .long("answers")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("save-answers")
+                    .help("Save the values entered at prompts to FILE, for use with --answers. Secrets such as API_TOKEN are left out")
+                    .long("save-answers")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("seed")
+                    .help("Seed for :uuid, :random and :randint so output is reproducible")
+                    .long("seed")
+                    .takes_value(true)
+                    .validator(|s| s.parse::<u64>())
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("now")
+                    .help("Freeze the clock used for NOW, YYYY, MM, DD and :date, e.g. 2026-01-01T00:00:00Z")
+                    .long("now")
+                    .takes_value(true)
+                    .validator(chrono::DateTime::parse_from_rfc3339)
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("no-env")
+                    .help("Ignore the process environment: no HOME, CURRENTDIR or environment variables")
+                    .long("no-env")
+                    .takes_value(false)
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("allow-outside")
+                    .help("Trust the template to write files outside the output directory")
+                    .long("allow-outside")
+                    .takes_value(false)
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("output-dir")
+                    .help("Write the generated files under this directory instead of the current one")
+                    .long("output-dir")
+                    .short('o')
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("manifest")
+                    .help("Record the template, variables and file hashes in .spark/manifest.toml")
+                    .long("manifest")
+                    .takes_value(false)
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("strict")
+                    .help("Fail listing every placeholder without a value instead of leaving it empty")
+                    .long("strict")
+                    .takes_value(false)
+                    .requires("template"),
+            )
+            .arg(
+                Arg::new("no-input")
+                    .help("Never prompt: use defaults and fail on values that would be asked for. Implied when stdin is not a terminal")
+                    .long("no-input")
+                    .alias("yes"),
+            )
+            .arg(
+                Arg::new("deny-unknown-keys")
+                    .help("Refuse to render a template containing keys spark does not understand")
+                    .long("deny-unknown-keys")
+                    .takes_value(false)
+                    .requires("template"),
+            )
+            .subcommand(
+                Command::new("init")
+                    .about("Creates a template for the current directory")
+                    .arg(
+                        Arg::new("template-name")
+                            .help("Name of the template file, defaults to the directory name")
+                            .takes_value(true)
+                            .index(1),
+                    )
+                    .arg(
+                        Arg::new("output")
+                            .help("Write the template to this path instead of the templates directory")
+                            .long("output")
+                            .short('o')
+                            .takes_value(true)
+                            .value_name("PATH"),
+                    )
+                    .arg(
+                        Arg::new("stdout")
+                            .help("Print the template instead of writing it; implies --yes")
+                            .long("stdout")
+                            .takes_value(false)
+                            .conflicts_with_all(&["output", "force"]),
+                    )
+                    .arg(
+                        Arg::new("force")
+                            .help("Overwrite an existing template")
+                            .long("force")
+                            .short('f')
+                            .takes_value(false),
+                    )
+                    .arg(
+                        Arg::new("name")
+                            .help("Template name recorded in [info]")
+                            .long("name")
+                            .takes_value(true),
+                    )
+                    .arg(
+                        Arg::new("description")
+                            .help("Template description recorded in [info]")
+                            .long("description")
+                            .takes_value(true),
+                    )
+                    .arg(
+                        Arg::new("author")
+                            .help("Template author recorded in [info]")
+                            .long("author")
+                            .takes_value(true),
+                    )
+                    .arg(
+                        Arg::new("var")
+                            .help("Replace VALUE with {{$NAME}} in paths and contents, e.g. --var ORG=acme")
+                            .long("var")
+                            .takes_value(true)
+                            .value_name("NAME=VALUE")
+                            .multiple_occurrences(true)
+                            .validator(|s| match s.split_once('=') {
+                                Some((name, value)) if !name.trim().is_empty() && !value.is_empty() => Ok(()),
+                                _ => Err("expected NAME=VALUE"),
+                            }),
+                    )
+                    .arg(
+                        Arg::new("yes")
+                            .help("Accept every suggested replacement and default without prompting")
+                            .long("yes")
+                            .short('y')
+                            .takes_value(false),
+                    ),
+            )
+            .subcommand(
+                Command::new("test")
+                    .about("Renders a template against the fixtures in <template>.tests/ and compares the output")
+                    .arg(
+                        Arg::new("template")
+                            .help("Template to test")
+                            .takes_value(true)
+                            .required(true)
+                            .index(1),
+                    )
+                    .arg(
+                        Arg::new("bless")
+                            .help("Overwrite the expected output with what the template renders now")
+                            .long("bless")
+                            .takes_value(false),
+                    )
+                    .arg(
+                        Arg::new("format")
+                            .help("Output format")
+                            .long("format")
+                            .takes_value(true)
+                            .possible_values(["text", "json"])
+                            .default_value("text"),
+                    ),
+            )
+            .subcommand(
+                Command::new("lint")
+                    .about("Checks a template for problems without rendering it")
+                    .arg(
+                        Arg::new("template")
+                            .help("Template to check")
+                            .takes_value(true)
+                            .required(true)
+                            .index(1),
+                    )
+                    .arg(
+                        Arg::new("format")
+                            .help("Output format")
+                            .long("format")
+                            .takes_value(true)
+                            .possible_values(["text", "json"])
+                            .default_value("text"),
+                    )
+                    .arg(
+                        Arg::new("allow-unknown-keys")
+                            .help("Do not report keys spark does not understand")
+                            .long("allow-unknown-keys")
+                            .takes_value(false),
+                    ),
+            )
+            .subcommand(
+                Command::new("update")
+                    .about("Updates a project generated with --manifest to the current version of its template")
+                    .arg(
+                        Arg::new("dir")
+                            .help("Project directory containing .spark/manifest.toml")
+                            .takes_value(true)
+                            .default_value(".")
+                            .index(1),
+                    )
+                    .arg(
+                        Arg::new("template")
+                            .help("Template to update to, instead of the one recorded in the manifest")
+                            .long("template")
+                            .short('t')
+                            .takes_value(true),
+                    )
+                    .arg(
+                        Arg::new("set")
+                            .help("Set one variable, e.g. a secret the manifest does not record; repeatable")
+                            .long("set")
+                            .takes_value(true)
+                            .value_name("NAME=VALUE")
+                            .multiple_occurrences(true)
+                            .validator(|s| vars::parse_assignment(s).map(|_| ())),
+                    )
+                    .arg(
+                        Arg::new("format")
+                            .help("Output format; json never prompts")
+                            .long("format")
+                            .takes_value(true)
+                            .possible_values(["text", "json"])
+                            .default_value("text"),
+                    ),
+            )
+            .subcommand(
+                Command::new("schema").about("Prints the JSON Schema of the template format"),
+            )
     }
 
     pub fn parse() -> clap::ArgMatches {
@@ -103,6 +390,187 @@ mod tests {
         assert_eq!(matches.value_of("config"), Some("/tmp/config.toml"));
     }
 
+    #[test]
+    fn parses_variable_flags_and_rejects_malformed_values() {
+        let matches = Cli::app()
+            .try_get_matches_from([
+                "spark",
+                "demo",
+                "--from",
+                "motto=\"fast, small\"",
+                "--set",
+                "url=https://x.io/?a=1",
+                "--set",
+                "b=2",
+                "--set-json",
+                "ports=[1,2]",
+                "--vars-file",
+                "vars.toml",
+                "--answers",
+                "answers.toml",
+                "--save-answers",
+                "next.toml",
+            ])
+            .unwrap();
+        let set: Vec<_> = matches.values_of("set").unwrap().collect();
+        assert_eq!(set, ["url=https://x.io/?a=1", "b=2"]);
+        assert_eq!(matches.value_of("set-json"), Some("ports=[1,2]"));
+        assert_eq!(matches.value_of("vars-file"), Some("vars.toml"));
+        assert_eq!(matches.value_of("answers"), Some("answers.toml"));
+        assert_eq!(matches.value_of("save-answers"), Some("next.toml"));
+
+        for bad in [
+            ["--from", "name"],
+            ["--set", "novalue"],
+            ["--set-json", "a=[1,"],
+        ] {
+            let result = Cli::app().try_get_matches_from(["spark", "demo", bad[0], bad[1]]);
+            assert!(result.is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn parses_output_format() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "demo", "--format", "json"])
+            .unwrap();
+        assert_eq!(matches.value_of("format"), Some("json"));
+
+        let result = Cli::app().try_get_matches_from(["spark", "demo", "--format", "xml"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_repeated_data_files() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "demo", "--data", "Cargo.toml", "-d", "-"])
+            .unwrap();
+        let data: Vec<_> = matches.values_of("data").unwrap().collect();
+        assert_eq!(data, ["Cargo.toml", "-"]);
+    }
+
+    #[test]
+    fn parses_seed_and_rejects_non_numeric_values() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "demo", "--seed", "42"])
+            .unwrap();
+        assert_eq!(matches.value_of_t::<u64>("seed").unwrap(), 42);
+
+        let result = Cli::app().try_get_matches_from(["spark", "demo", "--seed", "abc"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_now_and_rejects_non_rfc3339_values() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "demo", "--now", "2026-01-01T00:00:00Z"])
+            .unwrap();
+        assert_eq!(matches.value_of("now"), Some("2026-01-01T00:00:00Z"));
+
+        let result = Cli::app().try_get_matches_from(["spark", "demo", "--now", "2026-01-01"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_no_env() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "demo", "--no-env"])
+            .unwrap();
+        assert!(matches.is_present("no-env"));
+
+        let result = Cli::app().try_get_matches_from(["spark", "--no-env"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_output_dir() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "demo", "--output-dir", "out/demo"])
+            .unwrap();
+        assert_eq!(matches.value_of("output-dir"), Some("out/demo"));
+
+        assert!(Cli::app()
+            .try_get_matches_from(["spark", "-o", "out"])
+            .is_err());
+    }
+
+    #[test]
+    fn parses_test_subcommand() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "test", "demo", "--bless"])
+            .unwrap();
+        let test = matches.subcommand_matches("test").unwrap();
+        assert_eq!(test.value_of("template"), Some("demo"));
+        assert!(test.is_present("bless"));
+        assert_eq!(test.value_of("format"), Some("text"));
+
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "test", "demo", "--format", "json"])
+            .unwrap();
+        let test = matches.subcommand_matches("test").unwrap();
+        assert_eq!(test.value_of("format"), Some("json"));
+
+        assert!(Cli::app().try_get_matches_from(["spark", "test"]).is_err());
+    }
+
+    #[test]
+    fn parses_update_subcommand() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "update"])
+            .unwrap();
+        let update = matches.subcommand_matches("update").unwrap();
+        assert_eq!(update.value_of("dir"), Some("."));
+        assert_eq!(update.value_of("template"), None);
+
+        let matches = Cli::app()
+            .try_get_matches_from([
+                "spark",
+                "update",
+                "services/billing",
+                "-t",
+                "service",
+                "--format",
+                "json",
+                "--set",
+                "DB_PASSWORD=pw",
+            ])
+            .unwrap();
+        let update = matches.subcommand_matches("update").unwrap();
+        assert_eq!(update.value_of("dir"), Some("services/billing"));
+        assert_eq!(update.value_of("template"), Some("service"));
+        assert_eq!(update.value_of("format"), Some("json"));
+        assert_eq!(update.value_of("set"), Some("DB_PASSWORD=pw"));
+    }
+
+    #[test]
+    fn parses_lint_subcommand() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "lint", "demo", "--format", "json"])
+            .unwrap();
+        let lint = matches.subcommand_matches("lint").unwrap();
+        assert_eq!(lint.value_of("template"), Some("demo"));
+        assert_eq!(lint.value_of("format"), Some("json"));
+
+        let lenient = Cli::app()
+            .try_get_matches_from(["spark", "lint", "demo", "--allow-unknown-keys"])
+            .unwrap();
+        assert!(lenient
+            .subcommand_matches("lint")
+            .unwrap()
+            .is_present("allow-unknown-keys"));
+
+        let result = Cli::app().try_get_matches_from(["spark", "lint", "demo", "--format", "xml"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_schema_subcommand() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "schema"])
+            .unwrap();
+        assert!(matches.subcommand_matches("schema").is_some());
+    }
+
     #[test]
     fn default_config_path() {
         let matches = Cli::app().try_get_matches_from(["spark", "demo"]).unwrap();
@@ -118,6 +586,57 @@ mod tests {
         assert!(matches.subcommand_matches("init").is_some());
     }
 
+    #[test]
+    fn yes_is_an_alias_of_no_input() {
+        for flag in ["--no-input", "--yes"] {
+            let matches = Cli::app()
+                .try_get_matches_from(["spark", "service", flag])
+                .unwrap();
+            assert!(matches.is_present("no-input"));
+        }
+    }
+
+    #[test]
+    fn parses_init_destination() {
+        let matches = Cli::app()
+            .try_get_matches_from(["spark", "init", "service", "--output", "service.toml", "-f"])
+            .unwrap();
+        let init = matches.subcommand_matches("init").unwrap();
+        assert_eq!(init.value_of("template-name"), Some("service"));
+        assert_eq!(init.value_of("output"), Some("service.toml"));
+        assert!(init.is_present("force"));
+
+        let result = Cli::app().try_get_matches_from(["spark", "init", "--stdout", "-o", "x.toml"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_init_info_and_variables() {
+        let matches = Cli::app()
+            .try_get_matches_from([
+                "spark",
+                "init",
+                "--name",
+                "service",
+                "--var",
+                "ORG=acme",
+                "--var",
+                "PORT=8080",
+                "-y",
+            ])
+            .unwrap();
+        let init = matches.subcommand_matches("init").unwrap();
+        assert_eq!(init.value_of("name"), Some("service"));
+        assert_eq!(
+            init.values_of("var").unwrap().collect::<Vec<_>>(),
+            ["ORG=acme", "PORT=8080"]
+        );
+        assert!(init.is_present("yes"));
+
+        let result = Cli::app().try_get_matches_from(["spark", "init", "--var", "ORG"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn rejects_git_without_template() {
         let result = Cli::app().try_get_matches_from(["spark", "--git"]);
@@ -1,6 +1,7 @@
 use crate::Template;
-use colored::Colorize;
-use std::{collections::HashMap, fs, path::Path};
+use spark::events::{Event, Reporter};
+use spark::Context;
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
 use toml::Value;
 
 #[derive(Debug, Clone)]
@@ -19,11 +20,13 @@ impl Config {
 
         Self {
             path: config_path,
-            templates_path: shellexpand::tilde(templates.to_str().unwrap()).to_string(),
+            templates_path: templates.to_string_lossy().into_owned(),
         }
     }
 
-    pub fn init(self) {
+    /// Writes the config file and the built-in `new.toml` template, reporting
+    /// the files written to `reporter`.
+    pub fn init(self, reporter: Arc<dyn Reporter>) -> spark::Result<()> {
         // "initPJNAME" wtf is it ?
         // That's just a way to workaround auto replacing PROJECTNAME in templates
         let conf_template = r#"
@@ -62,12 +65,33 @@ content = '''
         keywords.insert("{{$CONFIGPATH}}".to_string(), self.path);
         keywords.insert("{{$TEMPLATES_PATH}}".to_string(), self.templates_path);
 
-        let mut template: Template = toml::from_str(conf_template).unwrap();
+        let template: Template = toml::from_str(conf_template)?;
+        let context = Context::from(keywords)
+            .trusted()
+            .non_interactive()
+            .with_reporter(reporter);
 
-        Template::extract(&mut template, &mut keywords).unwrap();
+        template.extract_with_context(&context).map(drop)
     }
 
-    pub fn get_keywords(&self) -> HashMap<String, String> {
+    /// Whether `path` is the `new.toml` written by [`Config::init`]. It creates
+    /// templates in the templates directory, so it is trusted like the config
+    /// itself. Both sides are canonicalized, so `./` or a symlinked config
+    /// directory still match and a look-alike path does not.
+    pub fn is_builtin_template(&self, path: &str) -> bool {
+        let builtin = Path::new(&self.templates_path).join("new.toml");
+        match (fs::canonicalize(builtin), fs::canonicalize(path)) {
+            (Ok(builtin), Ok(path)) => builtin == path,
+            _ => false,
+        }
+    }
+
+    /// Keywords from the `[Keywords]` table of the config, creating the config
+    /// first if it does not exist.
+    pub fn get_keywords(
+        &self,
+        reporter: Arc<dyn Reporter>,
+    ) -> spark::Result<HashMap<String, String>> {
         let mut keywords = HashMap::new();
 
         if let Ok(toml_str) = fs::read_to_string(&self.path) {
@@ -80,21 +104,21 @@ content = '''
                 }
             }
         } else {
-            eprintln!(
-                "\n[{}] Creating config files and templates for first-time setup...",
-                "INFO".bold().blue()
-            );
-            self.clone().init();
+            reporter.report(&Event::Info {
+                message: "Creating config files and templates for first-time setup...".to_string(),
+            });
+            self.clone().init(reporter)?;
         }
 
-        keywords
+        Ok(keywords)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Config;
-    use std::fs;
+    use spark::events::SilentReporter;
+    use std::{fs, sync::Arc};
 
     #[test]
     fn new_expands_tilde_and_derives_templates_path() {
@@ -131,7 +155,7 @@ GITHUB = "https://github.com/pwnxpl0it"
         .unwrap();
 
         let cfg = Config::new(&config_path.to_string_lossy());
-        let keywords = cfg.get_keywords();
+        let keywords = cfg.get_keywords(Arc::new(SilentReporter)).unwrap();
 
         assert_eq!(
             keywords.get("{$AUTHOR}").map(String::as_str),
@@ -155,7 +179,7 @@ GITHUB = "https://github.com/pwnxpl0it"
         fs::write(&config_path, "").unwrap();
 
         let cfg = Config::new(&config_path.to_string_lossy());
-        let keywords = cfg.get_keywords();
+        let keywords = cfg.get_keywords(Arc::new(SilentReporter)).unwrap();
         assert!(keywords.is_empty());
 
         let _ = fs::remove_dir_all(&dir);
@@ -169,7 +193,7 @@ GITHUB = "https://github.com/pwnxpl0it"
 
         let config_path = dir.join("config.toml");
         let cfg = Config::new(&config_path.to_string_lossy());
-        cfg.init();
+        cfg.init(Arc::new(SilentReporter)).unwrap();
 
         assert!(config_path.exists());
         let content = fs::read_to_string(&config_path).unwrap();
@@ -177,4 +201,26 @@ GITHUB = "https://github.com/pwnxpl0it"
 
         let _ = fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn builtin_template_is_matched_by_canonical_path() {
+        let dir = std::env::temp_dir().join("spark_test_config_builtin");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let cfg = Config::new(&dir.join("config.toml").to_string_lossy());
+        cfg.clone().init(Arc::new(SilentReporter)).unwrap();
+        let builtin = dir.join("templates/new.toml");
+        fs::copy(&builtin, dir.join("new.toml")).unwrap();
+
+        assert!(cfg.is_builtin_template(&builtin.to_string_lossy()));
+        assert!(cfg.is_builtin_template(
+            &dir.join("templates/../templates/./new.toml")
+                .to_string_lossy()
+        ));
+        assert!(!cfg.is_builtin_template(&dir.join("new.toml").to_string_lossy()));
+        assert!(!cfg.is_builtin_template(&dir.join("templates/missing.toml").to_string_lossy()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,190 @@
+//! Reporting errors from the CLI and mapping them to exit codes.
+//!
+//! Exit code 2 is left to clap, which uses it for invalid command lines.
+
+use colored::*;
+use spark::{events::Event, Error};
+
+/// A check such as `spark test`, `spark lint` or `spark update` found problems,
+/// or an error that fits no other category.
+pub const EXIT_FAILURE: i32 = 1;
+/// The template is missing, does not parse or fails to render.
+pub const EXIT_TEMPLATE: i32 = 3;
+/// A variable had no value and could not be prompted for.
+pub const EXIT_MISSING_VARIABLE: i32 = 4;
+/// `--json`, `--data` or `--vars-file` input could not be read or parsed.
+pub const EXIT_DATA: i32 = 5;
+/// Writing the output, or a post-generation step like `git init`, failed.
+pub const EXIT_OUTPUT: i32 = 6;
+
+/// A [`spark::Error`] with what the CLI knows about where it came from.
+#[derive(Debug)]
+pub struct CliError {
+    error: Error,
+    /// Path and text of the template being parsed, for a code frame.
+    template: Option<Box<(String, String)>>,
+    /// How to get past the error, printed after it.
+    hint: Option<String>,
+}
+
+impl CliError {
+    /// Attaches the template the error happened in.
+    pub fn in_template(mut self, path: &str, content: &str) -> Self {
+        self.template = Some(Box::new((path.to_string(), content.to_string())));
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self.error {
+            Error::TomlDe(_)
+            | Error::UnknownKeys(_)
+            | Error::Regex(_)
+            | Error::Liquid(_)
+            | Error::JsonFilter(_)
+            | Error::UnknownFunction(_)
+            | Error::InvalidPath(_) => EXIT_TEMPLATE,
+            Error::MissingVariable(_) | Error::MissingVariables(_) | Error::Prompt(_) => {
+                EXIT_MISSING_VARIABLE
+            }
+            Error::Json(_) | Error::Data { .. } => EXIT_DATA,
+            Error::Io(_)
+            | Error::OutputWrite { .. }
+            | Error::PathEscape { .. }
+            | Error::Hook(_) => EXIT_OUTPUT,
+            Error::TomlSer(_) | Error::Custom(_) => EXIT_FAILURE,
+        }
+    }
+
+    /// Prints the error, a code frame for TOML syntax errors and the hint to stderr.
+    pub fn report(&self) {
+        eprintln!("{}: {}", "error".red().bold(), self.error);
+
+        if let (Error::TomlDe(e), Some((path, content))) = (&self.error, self.template.as_deref()) {
+            if let Some((line, column)) = e.line_col() {
+                eprint!("{}", code_frame(path, content, line, column));
+            }
+        }
+        if let Some(hint) = &self.hint {
+            eprintln!("{}: {}", "hint".cyan().bold(), hint);
+        }
+    }
+
+    /// Prints the error as an [`Event::Error`] JSON line on stdout, with the
+    /// hint in its message.
+    pub fn report_json(&self) {
+        let mut message = self.error.to_string();
+        if let Some(hint) = &self.hint {
+            message = format!("{} ({})", message, hint);
+        }
+        let event = Event::Error {
+            kind: self.error.kind().to_string(),
+            message,
+        };
+        crate::print_event(&event);
+    }
+}
+
+impl From<Error> for CliError {
+    fn from(error: Error) -> Self {
+        Self {
+            error,
+            template: None,
+            hint: None,
+        }
+    }
+}
+
+/// Source excerpt pointing at `line`/`column` (both 0-based), with the line
+/// before it for context:
+///
+/// ```text
+///   --> service.toml:3:8
+///    |
+///  2 | [[files]]
+///  3 | path = src/main.rs
+///    |        ^
+/// ```
+fn code_frame(path: &str, content: &str, line: usize, column: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(text) = lines.get(line) else {
+        return format!("  --> {}:{}:{}\n", path, line + 1, column + 1);
+    };
+    let width = (line + 1).to_string().len();
+    let gutter = " ".repeat(width);
+
+    let mut frame = format!(
+        "{}{} {}:{}:{}\n{} {}\n",
+        gutter,
+        "-->".blue().bold(),
+        path,
+        line + 1,
+        column + 1,
+        gutter,
+        "|".blue().bold()
+    );
+    if let Some(previous) = line.checked_sub(1).and_then(|i| lines.get(i)) {
+        frame += &format!("{:>width$} {} {}\n", line, "|".blue().bold(), previous);
+    }
+    frame += &format!("{:>width$} {} {}\n", line + 1, "|".blue().bold(), text);
+    frame += &format!(
+        "{} {} {}{}\n",
+        gutter,
+        "|".blue().bold(),
+        " ".repeat(column),
+        "^".red().bold()
+    );
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_frame_points_at_the_column() {
+        colored::control::set_override(false);
+        let frame = code_frame("t.toml", "[[files]]\npath = oops\n", 1, 7);
+        assert_eq!(
+            frame,
+            " --> t.toml:2:8\n  |\n1 | [[files]]\n2 | path = oops\n  |        ^\n"
+        );
+    }
+
+    #[test]
+    fn errors_map_to_category_exit_codes() {
+        let code = |error: Error| CliError::from(error).exit_code();
+        assert_eq!(
+            code(Error::MissingVariable("X".into())),
+            EXIT_MISSING_VARIABLE
+        );
+        assert_eq!(code(Error::InvalidPath("t.toml".into())), EXIT_TEMPLATE);
+        assert_eq!(
+            code(Error::Data {
+                path: "-".into(),
+                message: "bad".into()
+            }),
+            EXIT_DATA
+        );
+        assert_eq!(code(Error::Hook("git".into())), EXIT_OUTPUT);
+
+        let toml_error = toml::from_str::<toml::Value>("a = ").unwrap_err();
+        assert_eq!(code(toml_error.into()), EXIT_TEMPLATE);
+    }
+
+    #[test]
+    fn unknown_liquid_filter_is_a_template_error() {
+        let template = spark::Template::from_str(
+            "[options]\nuse_liquid = true\n\n[[files]]\npath = \"a.txt\"\ncontent = \"{{ 'x' | shout }}\"\n",
+        )
+        .unwrap();
+        let error = template.render(&spark::Context::new()).unwrap_err();
+
+        assert!(matches!(error, Error::Liquid(_)), "{:?}", error);
+        assert_eq!(CliError::from(error).exit_code(), EXIT_TEMPLATE);
+    }
+}
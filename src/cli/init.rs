@@ -0,0 +1,155 @@
+use crate::config::Config;
+use crate::errors::CliError;
+use clap::ArgMatches;
+use colored::*;
+use promptly::{prompt_default, prompt_opt};
+use spark::templates::options::git;
+use spark::{Capture, Error, Information, Suggestion};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where `spark init` puts the generated template.
+enum Destination {
+    Stdout,
+    File(PathBuf),
+}
+
+/// `spark init [NAME]`: captures the current directory into a template, offering
+/// to replace the project name and other repeated strings with variables.
+///
+/// The template is written to `<templates_path>/<NAME>.toml` unless `--output` or
+/// `--stdout` is given, and an existing file is only replaced with `--force`.
+/// Without `interactive`, every suggestion and default is accepted as with `--yes`.
+pub fn run(args: &ArgMatches, config: &Config, interactive: bool) -> Result<(), CliError> {
+    let mut capture = Capture::scan(Path::new("."))?;
+    let name = args
+        .value_of("template-name")
+        .map(str::to_string)
+        .unwrap_or_else(|| capture.name.clone());
+
+    let destination = if args.is_present("stdout") {
+        Destination::Stdout
+    } else {
+        let path = match args.value_of("output") {
+            Some(output) => PathBuf::from(shellexpand::tilde(output).as_ref()),
+            None => Path::new(&config.templates_path).join(format!("{}.toml", name)),
+        };
+        if path.exists() && !args.is_present("force") {
+            let error = Error::OutputWrite {
+                path: path.display().to_string(),
+                message: "the template already exists".to_string(),
+            };
+            return Err(CliError::from(error).with_hint("Use --force to overwrite it."));
+        }
+        Destination::File(path)
+    };
+
+    if let Destination::File(path) = &destination {
+        eprintln!(
+            "{}: {}",
+            "Creating Template".bold().green(),
+            path.display().to_string().yellow()
+        );
+        // A template written inside the captured directory must not capture itself.
+        capture
+            .files
+            .retain(|file| !same_path(Path::new(&file.path), path));
+    }
+
+    for path in &capture.skipped {
+        eprintln!("{}: {}", "skipped binary file".yellow(), path.display());
+    }
+
+    let yes = args.is_present("yes") || !interactive || matches!(destination, Destination::Stdout);
+    let author = git::config_value("user.name");
+    let mut candidates: Vec<(String, String)> = [
+        ("AUTHOR", author.clone()),
+        ("EMAIL", git::config_value("user.email")),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((name.to_string(), value?)))
+    .collect();
+    for var in args.values_of("var").into_iter().flatten() {
+        let (name, value) = var.split_once('=').expect("validated by clap");
+        candidates.push((name.to_string(), value.to_string()));
+    }
+
+    let accepted: Vec<Suggestion> = capture
+        .suggest(&candidates)
+        .into_iter()
+        .filter(|s| yes || confirm(s))
+        .collect();
+
+    let info = Information::new(
+        ask(args, "name", "Template name", Some(name), yes),
+        ask(args, "author", "Author", author, yes),
+        ask(args, "description", "Description", None, yes),
+    );
+
+    let template = capture.into_template(&accepted, info);
+    let toml_string = toml::to_string_pretty(&template).map_err(Error::from)?;
+    let path = match destination {
+        Destination::Stdout => {
+            print!("{}", toml_string);
+            return Ok(());
+        }
+        Destination::File(path) => path,
+    };
+
+    let written = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, toml_string));
+    written.map_err(|e| Error::OutputWrite {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+
+    eprintln!(
+        "{}: Template successfully generated at {}",
+        "Success".green().bold(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Whether `relative` (a captured path) and `path` name the same file.
+fn same_path(relative: &Path, path: &Path) -> bool {
+    match (fs::canonicalize(relative), fs::canonicalize(path)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn confirm(suggestion: &Suggestion) -> bool {
+    let question = format!(
+        "Replace '{}' ({} occurrences) with {}?",
+        suggestion.value, suggestion.occurrences, suggestion.replacement
+    );
+    prompt_default(question, true).unwrap_or(false)
+}
+
+/// `[info]` field from its flag, or prompted for with `default` unless `--yes`.
+fn ask(
+    args: &ArgMatches,
+    flag: &str,
+    label: &str,
+    default: Option<String>,
+    yes: bool,
+) -> Option<String> {
+    if let Some(value) = args.value_of(flag) {
+        return Some(value.to_string());
+    }
+    if yes {
+        return default;
+    }
+
+    let answer = match default {
+        Some(default) => prompt_default(label, default).ok(),
+        None => prompt_opt::<String, _>(label).ok().flatten(),
+    };
+    answer.filter(|value| !value.trim().is_empty())
+}
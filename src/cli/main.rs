@@ -1,80 +1,327 @@
 use crate::config::*;
+use chrono::DateTime;
+use clap::ArgMatches;
 use colored::*;
 use dotenv::dotenv;
-use spark::{Keywords, Template};
+use errors::{CliError, EXIT_FAILURE};
+use spark::events::{Event, Reporter, SilentReporter, TerminalReporter};
+use spark::templates::manifest::is_secret;
+use spark::Error;
+use spark::{
+    data, Context, Environment, Keywords, Manifest, Severity, SnapshotDiff, Template, UpdateStatus,
+};
 use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::Arc;
+mod answers;
 mod args;
 mod config;
+mod errors;
+mod init;
+mod vars;
+use answers::Answers;
 use args::Cli;
 
 fn main() {
     let args = Cli::parse();
+    if let Err(e) = run(&args) {
+        if json_output(&args) {
+            e.report_json();
+        } else {
+            e.report();
+        }
+        std::process::exit(e.exit_code());
+    }
+}
+
+/// Whether `--format json` was given: events are printed as JSON lines
+/// instead of colored text.
+fn json_output(args: &ArgMatches) -> bool {
+    args.value_of("format") == Some("json")
+}
+
+/// Whether spark may prompt: not with `--no-input` or `--format json`, nor when
+/// stdin is not a terminal, e.g. in CI.
+fn interactive(args: &ArgMatches) -> bool {
+    !args.is_present("no-input") && !json_output(args) && io::stdin().is_terminal()
+}
+
+fn print_event(event: &Event) {
+    println!(
+        "{}",
+        serde_json::to_string(event).expect("events serialize to JSON")
+    );
+}
+
+fn run(args: &ArgMatches) -> Result<(), CliError> {
+    // Handled before the config is read, which may print while creating it.
+    if args.subcommand_matches("schema").is_some() {
+        print!("{}", Template::json_schema_string());
+        return Ok(());
+    }
+
+    let reporter: Arc<dyn Reporter> = if json_output(args) {
+        Arc::new(print_event)
+    } else {
+        Arc::new(TerminalReporter)
+    };
     let config = Config::new(args.value_of("config").unwrap());
     dotenv().ok();
-    let mut keywords = Keywords::init();
-    keywords.extend(config.clone().get_keywords());
-
-    if args.is_present("keywords") {
-        let pairs = args.value_of("keywords").unwrap();
-        let keys: Vec<&str> = pairs.split(',').collect();
-        for key in keys {
-            let (keyword, value) = key.split_once('=').unwrap();
-            keywords.insert(
-                Keywords::from(keyword.trim(), None),
-                value.trim().to_string(),
-            );
-        }
+    let now = args
+        .value_of("now")
+        .map(|now| DateTime::parse_from_rfc3339(now).expect("validated by clap"));
+    let environment = match args.is_present("no-env") {
+        true => Environment::new(),
+        false => Environment::capture(),
+    };
+    let mut keywords = config.get_keywords(reporter.clone())?;
+
+    // Later sources win: --answers, --vars-file, then --from, then each --set.
+    let replayed = match args.value_of("answers") {
+        Some(path) => vars::load_vars_file(path)?,
+        None => Vec::new(),
+    };
+    let answers = Answers::new(&replayed);
+    let mut vars = replayed;
+    if let Some(path) = args.value_of("vars-file") {
+        vars.extend(vars::load_vars_file(path)?);
+    }
+    if let Some(pairs) = args.value_of("keywords") {
+        vars.extend(spark::vars::parse_from(pairs).expect("validated by clap"));
+    }
+    for set in args.values_of("set").into_iter().flatten() {
+        vars.push(vars::parse_assignment(set).expect("validated by clap"));
+    }
+    for (keyword, value) in vars {
+        keywords.insert(Keywords::from(&keyword, None), value);
     }
 
-    if args.subcommand_matches("init").is_some() {
-        let dest = format!(
-            "{}.toml",
-            std::env::current_dir()
-                .unwrap()
-                .file_name()
-                .unwrap()
-                .to_str()
-                .unwrap()
-        );
-        println!("{}: {}", "Creating Template".bold().green(), dest.yellow());
-        Template::generate(&dest).expect("Failed to generate template");
-    } else if let Some(temp) = args.value_of("template") {
-        let mut template = temp.to_string();
+    if let Some(init) = args.subcommand_matches("init") {
+        init::run(init, &config, interactive(args))?;
+    } else if let Some(test) = args.subcommand_matches("test") {
+        let full_template_path = template_path(&config, test.value_of("template").unwrap());
+        let template = load_template(&config, &full_template_path, false)?;
+        let outcomes = template.test_snapshots(test.is_present("bless"))?;
+
+        if test.value_of("format") == Some("json") {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&outcomes).map_err(Error::from)?
+            );
+        } else {
+            for outcome in &outcomes {
+                if outcome.blessed {
+                    println!("{} {}", "blessed".blue(), outcome.case.bold());
+                } else if outcome.passed() {
+                    println!("{} {}", "ok".green(), outcome.case.bold());
+                } else {
+                    println!("{} {}", "FAILED".red().bold(), outcome.case.bold());
+                    for diff in &outcome.diffs {
+                        match diff {
+                            SnapshotDiff::Missing(path) => println!("  - {} (not rendered)", path),
+                            SnapshotDiff::Unexpected(path) => {
+                                println!("  + {} (not in snapshot)", path)
+                            }
+                            SnapshotDiff::Changed { path, line, .. } => {
+                                println!("  ~ {} (differs from line {})", path, line)
+                            }
+                        }
+                    }
+                }
+            }
+            if outcomes.is_empty() {
+                println!(
+                    "{}: {}",
+                    "No snapshot cases found for".yellow(),
+                    full_template_path.magenta()
+                );
+            }
+        }
 
-        if !template.ends_with(".toml") {
-            template.push_str(".toml");
+        if outcomes.iter().any(|o| !o.passed()) {
+            std::process::exit(EXIT_FAILURE);
+        }
+    } else if let Some(lint) = args.subcommand_matches("lint") {
+        let full_template_path = template_path(&config, lint.value_of("template").unwrap());
+        let template = load_template(&config, &full_template_path, false)?;
+        let mut diagnostics = template.lint()?;
+        if lint.is_present("allow-unknown-keys") {
+            diagnostics.retain(|d| d.code != "unknown-key");
         }
 
-        let full_template_path = if fs::read_to_string(&template).is_err() {
-            format!("{}/{}", config.templates_path, template)
+        if lint.value_of("format") == Some("json") {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&diagnostics).map_err(Error::from)?
+            );
         } else {
-            template
+            for diagnostic in &diagnostics {
+                let severity = match diagnostic.severity {
+                    Severity::Error => diagnostic.severity.to_string().red().bold(),
+                    Severity::Warning => diagnostic.severity.to_string().yellow().bold(),
+                };
+                let position = match (diagnostic.line, diagnostic.column) {
+                    (Some(line), Some(column)) => format!(":{}:{}", line, column),
+                    _ => String::new(),
+                };
+                println!(
+                    "{}{}: {}[{}]: {}",
+                    full_template_path.magenta(),
+                    position,
+                    severity,
+                    diagnostic.code,
+                    diagnostic.message
+                );
+            }
+            if diagnostics.is_empty() {
+                println!(
+                    "{}: {}",
+                    "No problems found in".green(),
+                    full_template_path.magenta()
+                );
+            }
+        }
+
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            std::process::exit(EXIT_FAILURE);
+        }
+    } else if let Some(update) = args.subcommand_matches("update") {
+        let project_dir = Path::new(update.value_of("dir").unwrap());
+        let manifest = Manifest::read(project_dir).map_err(|e| {
+            CliError::from(e).with_hint("Only projects generated with --manifest can be updated.")
+        })?;
+        let full_template_path = match update.value_of("template") {
+            Some(name) => template_path(&config, name),
+            None => manifest.template.source.ok_or_else(|| {
+                CliError::from(Error::from(
+                    "the manifest does not record the template source",
+                ))
+                .with_hint("Pass the template with --template.")
+            })?,
         };
+        let template = load_template(&config, &full_template_path, false)?;
 
-        let template_content = fs::read_to_string(&full_template_path).unwrap_or_else(|_| {
-            panic!(
-                "{}: {}",
-                "Failed to read template".red().bold(),
-                full_template_path
+        let json = update.value_of("format") == Some("json");
+        let reporter: Arc<dyn Reporter> = match json {
+            true => Arc::new(SilentReporter),
+            false => reporter,
+        };
+        for set in update.values_of("set").into_iter().flatten() {
+            let (name, value) = vars::parse_assignment(set).expect("validated by clap");
+            keywords.insert(Keywords::from(&name, None), value);
+        }
+        // Secrets are not saved in the manifest, so they have to be passed again.
+        let secrets: Vec<String> = template
+            .variable_names()
+            .into_iter()
+            .filter(|name| is_secret(name) && !keywords.contains_key(&Keywords::from(name, None)))
+            .collect();
+        let secrets_hint = (!secrets.is_empty()).then(|| {
+            format!(
+                "Secrets are not saved in the manifest, pass them with --set: {}",
+                secrets.join(", ")
             )
         });
+        let mut context = Context::from_environment(&environment).with_reporter(reporter.clone());
+        if let Some(now) = now {
+            context = context.with_now(now);
+        }
+        let context = context
+            .with_vars(keywords)
+            .with_interactive(interactive(args) && !json);
+        let report = template.update(project_dir, &context).map_err(|e| {
+            let missing = matches!(e, Error::MissingVariable(_) | Error::MissingVariables(_));
+            match &secrets_hint {
+                Some(hint) if missing => CliError::from(e).with_hint(hint.clone()),
+                _ => CliError::from(e),
+            }
+        })?;
+        // Without a prompt the missing secrets were rendered empty.
+        if let (Some(hint), false) = (secrets_hint, context.is_interactive()) {
+            reporter.report(&Event::Warning { message: hint });
+        }
+
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).map_err(Error::from)?
+            );
+        } else {
+            for file in &report.files {
+                let status = match file.status {
+                    UpdateStatus::Conflict => file.status.to_string().red().bold(),
+                    UpdateStatus::Kept | UpdateStatus::Skipped | UpdateStatus::Binary => {
+                        file.status.to_string().yellow()
+                    }
+                    _ => file.status.to_string().green(),
+                };
+                println!("{:>8} {}", status, file.path);
+            }
+            if report.files.is_empty() {
+                println!("{}", "Already up to date".green());
+            }
+            if report.has_conflicts() {
+                eprintln!(
+                    "\n{}: resolve the conflict markers, then commit the result",
+                    "conflicts".red().bold()
+                );
+            }
+        }
+
+        if report.has_conflicts() {
+            std::process::exit(EXIT_FAILURE);
+        }
+    } else if let Some(temp) = args.value_of("template") {
+        let full_template_path = template_path(&config, temp);
 
-        let mut parsed_template: Template = toml::from_str(&template_content).unwrap();
+        let mut parsed_template = load_template(
+            &config,
+            &full_template_path,
+            args.is_present("deny-unknown-keys"),
+        )?;
 
-        if !args.is_present("quiet") {
+        if !args.is_present("quiet") && !json_output(args) {
             println!(
                 "\n{}: {}",
                 "Using Template".blue(),
                 full_template_path.magenta()
             );
-            Template::show_info(&parsed_template);
+            if let Some(info) = &parsed_template.info {
+                for (label, value) in info.summary() {
+                    println!("{}: {}", label.yellow(), value.bold().green());
+                }
+                println!();
+            }
         }
 
         let mut options = parsed_template.dump_options().unwrap_or_default();
 
-        if args.is_present("json") {
-            let json_file = fs::read_to_string(args.value_of("json").unwrap());
-            let json_data = serde_json::from_str(&json_file.unwrap()).unwrap();
+        if let Some(path) = args.value_of("json") {
+            let data_error = |message: String| Error::Data {
+                path: path.to_string(),
+                message,
+            };
+            let json_file = fs::read_to_string(path).map_err(|e| data_error(e.to_string()))?;
+            let json_data =
+                serde_json::from_str(&json_file).map_err(|e| data_error(e.to_string()))?;
+            options.set_json(json_data);
+        }
+
+        if let Some(files) = args.values_of("data") {
+            let mut json_data = options.json_data.take().unwrap_or_default();
+            for file in files {
+                data::merge(&mut json_data, data::load(file)?);
+            }
+            options.set_json(json_data);
+        }
+
+        if let Some(values) = args.values_of("set-json") {
+            let mut json_data = options.json_data.take().unwrap_or_default();
+            for value in values {
+                let value = vars::parse_json_assignment(value).expect("validated by clap");
+                data::merge(&mut json_data, value);
+            }
             options.set_json(json_data);
         }
 
@@ -88,7 +335,62 @@ fn main() {
         }
 
         parsed_template.set_options(options);
-        parsed_template.extract(&mut keywords).unwrap();
+
+        let mut context = Context::from_environment(&environment)
+            .with_reporter(answers.recorder(reporter.clone()));
+        if let Some(now) = now {
+            context = context.with_now(now);
+        }
+        context = context
+            .with_vars(keywords)
+            .with_trusted(
+                args.is_present("allow-outside") || config.is_builtin_template(&full_template_path),
+            )
+            .with_interactive(interactive(args));
+        if let Some(dir) = args.value_of("output-dir") {
+            context = context.with_output_root(shellexpand::tilde(dir).as_ref());
+        }
+        context = context
+            .with_manifest(args.is_present("manifest"))
+            .with_strict(args.is_present("strict"));
+        if args.is_present("seed") {
+            context = context.with_seed(args.value_of_t("seed").expect("validated by clap"));
+        }
+
+        let extracted = parsed_template.extract_with_context(&context);
+        // Saved even if the extraction failed, so the answers are not lost.
+        if let Some(path) = args.value_of("save-answers") {
+            if answers.is_empty() {
+                reporter.report(&Event::Warning {
+                    message: "No answers to save".to_string(),
+                });
+            } else {
+                answers.save(path)?;
+                reporter.report(&Event::Info {
+                    message: format!("Answers saved to {}", path),
+                });
+            }
+            let secrets = answers.secrets();
+            if !secrets.is_empty() {
+                reporter.report(&Event::Warning {
+                    message: format!(
+                        "Secrets are not saved, pass them with --set when replaying: {}",
+                        secrets.join(", ")
+                    ),
+                });
+            }
+        }
+        extracted.map_err(|e| match e {
+            Error::PathEscape { .. } => {
+                CliError::from(e).with_hint("Use --allow-outside if you trust this template.")
+            }
+            Error::MissingVariable(_) | Error::MissingVariables(_) if !context.is_interactive() => {
+                CliError::from(e).with_hint(
+                    "Pass them with --set, --from, --vars-file or --answers, or run in a terminal to be asked.",
+                )
+            }
+            e => e.into(),
+        })?;
     } else {
         eprintln!(
             "{} {}",
@@ -96,4 +398,46 @@ fn main() {
             "--help".bold().green()
         );
     }
+
+    Ok(())
+}
+
+/// Reads and parses the template at `path`, keeping its text so syntax errors
+/// can be shown in context.
+fn load_template(config: &Config, path: &str, strict: bool) -> Result<Template, CliError> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            CliError::from(Error::InvalidPath(path.into())).with_hint(format!(
+                "Templates are looked up in the working directory and in {}",
+                config.templates_path
+            ))
+        } else {
+            Error::from(e).into()
+        }
+    })?;
+
+    let parsed = if strict {
+        Template::from_str_strict(&content)
+    } else {
+        Template::from_str(&content)
+    };
+    parsed
+        .map(|template| template.with_source(path))
+        .map_err(|e| CliError::from(e).in_template(path, &content))
+}
+
+/// Resolves a template name to a file path: `name` and `name.toml` are tried
+/// relative to the working directory first, then inside the templates directory.
+fn template_path(config: &Config, name: &str) -> String {
+    let mut template = name.to_string();
+
+    if !template.ends_with(".toml") {
+        template.push_str(".toml");
+    }
+
+    if fs::read_to_string(&template).is_err() {
+        format!("{}/{}", config.templates_path, template)
+    } else {
+        template
+    }
 }
@@ -0,0 +1,83 @@
+//! Variables given on the command line: `--set`, `--set-json` and `--vars-file`.
+//! `--from` lists are parsed by [`spark::vars::parse_from`].
+
+use serde_json::Value;
+use spark::{data, Error};
+
+/// Parses a `--set NAME=VALUE`. Everything after the first `=` is the value,
+/// taken as is.
+pub fn parse_assignment(input: &str) -> Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        Some(_) => Err(format!("missing variable name in '{}'", input)),
+        None => Err(format!("expected NAME=VALUE, got '{}'", input)),
+    }
+}
+
+/// Parses a `--set-json PATH=JSON` into the data it adds: `db.port=5432`
+/// becomes `{"db": {"port": 5432}}`, available as `{{$.db.port}}`.
+pub fn parse_json_assignment(input: &str) -> Result<Value, String> {
+    let (path, json) = parse_assignment(input)?;
+    let value: Value =
+        serde_json::from_str(&json).map_err(|e| format!("invalid JSON for '{}': {}", path, e))?;
+
+    if path.split('.').any(str::is_empty) {
+        return Err(format!("invalid path '{}'", path));
+    }
+    Ok(path
+        .rsplit('.')
+        .fold(value, |value, key| serde_json::json!({ key: value })))
+}
+
+/// Reads a `--vars-file`: a JSON, YAML, TOML or `.env` file with one value per
+/// variable. Numbers and booleans are converted to strings; nested tables and
+/// lists are rejected, use `--data` for those.
+pub fn load_vars_file(path: &str) -> spark::Result<Vec<(String, String)>> {
+    let data_error = |message: String| Error::Data {
+        path: path.to_string(),
+        message,
+    };
+    let Value::Object(map) = data::load(path)? else {
+        return Err(data_error("expected a table of NAME = VALUE".to_string()));
+    };
+
+    map.into_iter()
+        .map(|(name, value)| match value {
+            Value::String(s) => Ok((name, s)),
+            Value::Number(_) | Value::Bool(_) => Ok((name, value.to_string())),
+            Value::Null => Ok((name, String::new())),
+            Value::Array(_) | Value::Object(_) => Err(data_error(format!(
+                "'{}' is not a single value, pass structured data with --data",
+                name
+            ))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_keeps_everything_after_the_first_equals() {
+        assert_eq!(
+            parse_assignment("URL=https://x.io/?a=1,b=2").unwrap(),
+            ("URL".to_string(), "https://x.io/?a=1,b=2".to_string())
+        );
+        assert!(parse_assignment("URL").is_err());
+    }
+
+    #[test]
+    fn set_json_nests_dotted_paths() {
+        assert_eq!(
+            parse_json_assignment("db.ports=[1,2]").unwrap(),
+            serde_json::json!({ "db": { "ports": [1, 2] } })
+        );
+        assert!(parse_json_assignment("db=[1,")
+            .unwrap_err()
+            .contains("invalid JSON for 'db'"));
+        assert!(parse_json_assignment("db..port=1").is_err());
+    }
+}
@@ -1,6 +1,15 @@
 use crate::error::Result;
+use crate::events::{Event, Events, Reporter};
+use crate::funcs::FunctionRegistry;
+use crate::keywords::Environment;
 use crate::Keywords;
+use chrono::{DateTime, FixedOffset, Local};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 /// Execution context for resolving placeholders, JSON paths, and managing interactivity.
 #[derive(Debug, Clone)]
@@ -8,6 +17,16 @@ pub struct Context {
     pub(crate) keywords: HashMap<String, String>,
     pub(crate) json_data: Option<serde_json::Value>,
     pub(crate) interactive: bool,
+    pub(crate) functions: FunctionRegistry,
+    pub(crate) seed: Option<u64>,
+    pub(crate) now: Option<DateTime<FixedOffset>>,
+    pub(crate) environment: Option<Environment>,
+    pub(crate) builtins: OnceLock<HashMap<String, String>>,
+    pub(crate) trusted: bool,
+    pub(crate) output_root: Option<PathBuf>,
+    pub(crate) manifest: bool,
+    pub(crate) strict: bool,
+    pub(crate) events: Events,
 }
 
 impl Default for Context {
@@ -23,17 +42,75 @@ impl Context {
             keywords: HashMap::new(),
             json_data: None,
             interactive: true,
+            functions: FunctionRegistry::default(),
+            seed: None,
+            now: None,
+            environment: None,
+            builtins: OnceLock::new(),
+            trusted: false,
+            output_root: None,
+            manifest: false,
+            strict: false,
+            events: Events::default(),
         }
     }
 
-    /// Initializes a context pre-populated with standard built-in variables
-    /// (`{{$NOW}}`, `{{$YYYY}}`, `{{$HOME}}`, environment variables, etc.).
+    /// Initializes a context with the standard built-in variables
+    /// (`{{$NOW}}`, `{{$YYYY}}`, `{{$HOME}}`, environment variables, etc.),
+    /// taken from the live process environment.
     pub fn init() -> Self {
-        Self {
-            keywords: Keywords::init(),
-            json_data: None,
-            interactive: true,
-        }
+        Self::from_environment(&Environment::capture())
+    }
+
+    /// Builds a context whose built-in variables come from an explicit environment
+    /// snapshot instead of the live process (see [`Context::init`]).
+    ///
+    /// The built-ins are derived when rendering, underneath the variables set on
+    /// the context, so they never override [`Context::with_var`] whatever order
+    /// the builder methods are called in. Combine with [`Context::with_now`] for
+    /// fully reproducible renders.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::{Context, Environment};
+    ///
+    /// let env = Environment::new()
+    ///     .with_var("HOME", "/home/ci")
+    ///     .with_current_dir("/work/demo");
+    /// let now = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap();
+    ///
+    /// let ctx = Context::from_environment(&env).with_now(now);
+    /// assert_eq!(ctx.get_var("HOME"), Some("/home/ci"));
+    /// assert_eq!(ctx.get_var("CURRENTDIR"), Some("demo"));
+    /// assert_eq!(ctx.get_var("YYYY"), Some("2026"));
+    /// ```
+    pub fn from_environment(environment: &Environment) -> Self {
+        Self::new().with_environment(environment.clone())
+    }
+
+    /// Replaces the environment snapshot the built-in variables come from
+    /// (see [`Context::from_environment`]).
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self.builtins = OnceLock::new();
+        self
+    }
+
+    /// Freezes the clock: the date variables (`{{$NOW}}`, `{{$NOW_UTC}}`, `{{$YYYY}}`,
+    /// `{{$YY}}`, `{{$MM}}`, `{{$DD}}`) and the `:date(...)` function all use `now`.
+    ///
+    /// Variables set explicitly on the context still take precedence over the
+    /// date variables, regardless of call order.
+    pub fn with_now(mut self, now: DateTime<FixedOffset>) -> Self {
+        self.now = Some(now);
+        self.builtins = OnceLock::new();
+        self
+    }
+
+    /// Returns the time renders should use: the frozen clock if set, the current
+    /// local time otherwise.
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.now.unwrap_or_else(|| Local::now().fixed_offset())
     }
 
     /// Sets a template variable. If `key` is not already enclosed in `{{$...}}`,
@@ -121,12 +198,39 @@ impl Context {
         self
     }
 
+    /// Deep-merges `data` into the attached JSON data (see [`crate::data::merge`]),
+    /// so several sources can be layered with later ones taking precedence.
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        match &mut self.json_data {
+            Some(existing) => crate::data::merge(existing, data),
+            None => self.json_data = Some(data),
+        }
+        self
+    }
+
     /// Parses and attaches a JSON string for resolving `{{$.path.to.key}}` placeholders.
     pub fn with_json_str(self, json_str: &str) -> Result<Self> {
         let value: serde_json::Value = serde_json::from_str(json_str)?;
         Ok(self.with_json(value))
     }
 
+    /// Sends progress [events](crate::events) to `reporter`. Without one they are
+    /// dropped; use [`TerminalReporter`](crate::events::TerminalReporter) to print
+    /// them like the CLI does.
+    pub fn with_reporter(mut self, reporter: impl Reporter + 'static) -> Self {
+        self.events = Events::new(reporter);
+        self
+    }
+
+    /// Sends progress [events](crate::events) to `handler`. Same as
+    /// [`Context::with_reporter`] with a closure.
+    pub fn with_event_handler<F>(self, handler: F) -> Self
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        self.with_reporter(handler)
+    }
+
     /// Configures whether interactive terminal prompts (like `:read` or missing `PROJECTNAME`)
     /// are permitted. When set to `false`, missing variables will immediately return
     /// [`crate::Error::MissingVariable`] instead of blocking `stdin`.
@@ -141,12 +245,170 @@ impl Context {
         self
     }
 
+    /// Fails on placeholders without a value instead of rendering them empty.
+    /// Failed or `null` JSON lookups count as missing too, and all missing names
+    /// are reported together in [`crate::Error::MissingVariables`].
+    ///
+    /// ```rust
+    /// use spark::{Context, Error, File, Template};
+    ///
+    /// let template = Template::builder()
+    ///     .with_file(File::create("README.md", "# {{$NAME}} by {{$AUTHOR}}"));
+    ///
+    /// let err = template.render(&Context::new().strict()).unwrap_err();
+    /// assert!(matches!(err, Error::MissingVariables(names) if names == ["NAME", "AUTHOR"]));
+    /// ```
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Configures whether placeholders without a value are an error (see [`Context::strict`]).
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Marks the template as trusted, allowing it to write files outside the
+    /// output directory (absolute paths, `~`, `..`). Untrusted templates fail with
+    /// [`crate::Error::PathEscape`] instead.
+    pub fn trusted(mut self) -> Self {
+        self.trusted = true;
+        self
+    }
+
+    /// Configures whether the template may write files outside the output directory.
+    pub fn with_trusted(mut self, trusted: bool) -> Self {
+        self.trusted = trusted;
+        self
+    }
+
+    /// Writes relative file paths under `root` instead of the current directory.
+    ///
+    /// `project_root` (used for git init) resolves against it as well, and
+    /// untrusted templates are confined to it. The process working directory is
+    /// never changed, so contexts with different roots can be used concurrently.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::Context;
+    ///
+    /// let ctx = Context::new().with_output_root("/srv/projects/42");
+    /// assert_eq!(ctx.output_root(), Some(std::path::Path::new("/srv/projects/42")));
+    /// ```
+    pub fn with_output_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.output_root = Some(root.into());
+        self
+    }
+
+    /// Returns the directory relative file paths are written under, if set.
+    pub fn output_root(&self) -> Option<&Path> {
+        self.output_root.as_deref()
+    }
+
+    /// Configures whether [`Template::extract_with_context`](crate::Template::extract_with_context)
+    /// records a generation manifest (`.spark/manifest.toml`) in the project directory.
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Checks if extraction records a generation manifest.
+    pub fn records_manifest(&self) -> bool {
+        self.manifest
+    }
+
+    /// Registers a custom placeholder function usable as `{{$VAR:name}}`.
+    ///
+    /// The function receives the variable name and this context, and its result
+    /// becomes the value of both `{{$VAR:name}}` and `{{$VAR}}`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::{Context, File, Template};
+    ///
+    /// let ctx = Context::new()
+    ///     .with_function("ticket", |name, _ctx| Ok(format!("OPS-{}", name)))
+    ///     .non_interactive();
+    ///
+    /// let template = Template::builder()
+    ///     .with_file(File::create("NOTES.md", "Tracking {{$1234:ticket}}"));
+    ///
+    /// assert_eq!(template.render(&ctx)?[0].content, "Tracking OPS-1234");
+    /// # Ok::<(), spark::Error>(())
+    /// ```
+    pub fn with_function<F>(mut self, name: impl Into<String>, function: F) -> Self
+    where
+        F: Fn(&str, &Context) -> Result<String> + Send + Sync + 'static,
+    {
+        self.functions.register(name, function);
+        self
+    }
+
+    /// Returns a reference to the registered placeholder functions.
+    pub fn functions(&self) -> &FunctionRegistry {
+        &self.functions
+    }
+
+    /// Returns a mutable reference to the registered placeholder functions.
+    pub fn functions_mut(&mut self) -> &mut FunctionRegistry {
+        &mut self.functions
+    }
+
+    /// Seeds the generator functions (`:uuid`, `:random`, `:randint`) so every
+    /// render produces the same values.
+    ///
+    /// Each placeholder draws from its own stream derived from the seed and the
+    /// placeholder, so adding placeholders to a template does not change the
+    /// values generated for the existing ones.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::{Context, File, Template};
+    ///
+    /// let template = Template::builder().with_file(File::create("id", "{{$ID:uuid}}"));
+    /// let ctx = Context::new().with_seed(42).non_interactive();
+    ///
+    /// assert_eq!(template.render(&ctx)?, template.render(&ctx)?);
+    /// # Ok::<(), spark::Error>(())
+    /// ```
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the seed used for generator functions, if any.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Random number generator for the placeholder identified by `key`:
+    /// deterministic when a seed is set, seeded from the OS otherwise.
+    pub(crate) fn rng_for(&self, key: &str) -> ChaCha8Rng {
+        match self.seed {
+            Some(seed) => {
+                let digest = Sha256::new()
+                    .chain_update(seed.to_le_bytes())
+                    .chain_update(key.as_bytes())
+                    .finalize();
+                ChaCha8Rng::from_seed(digest.into())
+            }
+            None => ChaCha8Rng::from_entropy(),
+        }
+    }
+
     /// Checks if interactive prompts are enabled.
     pub fn is_interactive(&self) -> bool {
         self.interactive
     }
 
-    /// Returns a reference to the internal keywords map.
+    /// Checks if the template may write outside the output directory.
+    pub fn is_trusted(&self) -> bool {
+        self.trusted
+    }
+
+    /// Returns a reference to the variables set on the context. Built-in variables
+    /// are not included; [`Context::get_var`] falls back to them.
     pub fn keywords(&self) -> &HashMap<String, String> {
         &self.keywords
     }
@@ -161,13 +423,35 @@ impl Context {
         self.json_data.as_ref()
     }
 
-    /// Looks up the value of a variable.
-    pub fn get_var(&self, key: &str) -> Option<&str> {
-        if let Some(v) = self.keywords.get(key) {
-            return Some(v.as_str());
+    pub(crate) fn emit(&self, event: Event) {
+        self.events.emit(event);
+    }
+
+    /// Built-in variables for a render: everything derived from the environment
+    /// snapshot and the clock, or only the dates when the clock alone is frozen.
+    pub(crate) fn builtins(&self) -> HashMap<String, String> {
+        match &self.environment {
+            Some(environment) => Keywords::init_with(self.now(), environment),
+            None => self.now.map(Keywords::dates).unwrap_or_default(),
         }
+    }
+
+    /// Variables a render starts from: the built-ins, overridden by the
+    /// variables set on the context.
+    pub(crate) fn render_keywords(&self) -> HashMap<String, String> {
+        let mut keywords = self.builtins();
+        keywords.extend(self.keywords.clone());
+        keywords
+    }
+
+    /// Looks up the value of a variable, falling back to the built-ins.
+    pub fn get_var(&self, key: &str) -> Option<&str> {
         let formatted = Keywords::from(key, None);
-        self.keywords.get(&formatted).map(|s| s.as_str())
+        let builtins = self.builtins.get_or_init(|| self.builtins());
+        [&self.keywords, builtins]
+            .into_iter()
+            .find_map(|keywords| keywords.get(key).or_else(|| keywords.get(&formatted)))
+            .map(|s| s.as_str())
     }
 }
 
@@ -177,6 +461,16 @@ impl From<HashMap<String, String>> for Context {
             keywords,
             json_data: None,
             interactive: true,
+            functions: FunctionRegistry::default(),
+            seed: None,
+            now: None,
+            environment: None,
+            builtins: OnceLock::new(),
+            trusted: false,
+            output_root: None,
+            manifest: false,
+            strict: false,
+            events: Events::default(),
         }
     }
 }
@@ -192,6 +486,27 @@ mod tests {
         assert_eq!(ctx.get_var("{{$NAME}}"), Some("spark"));
     }
 
+    #[test]
+    fn explicit_vars_beat_builtins_in_any_order() {
+        let now = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap();
+        let env = Environment::new().with_var("HOME", "/home/ci");
+
+        let before = Context::from_environment(&env)
+            .with_now(now)
+            .with_vars([("YYYY", "1999"), ("HOME", "/srv")]);
+        let after = Context::new()
+            .with_vars([("YYYY", "1999"), ("HOME", "/srv")])
+            .with_now(now)
+            .with_environment(env);
+
+        for ctx in [before, after] {
+            assert_eq!(ctx.get_var("YYYY"), Some("1999"));
+            assert_eq!(ctx.get_var("HOME"), Some("/srv"));
+            assert_eq!(ctx.get_var("MM"), Some("1"));
+            assert_eq!(ctx.render_keywords()["{{$YYYY}}"], "1999");
+        }
+    }
+
     #[test]
     fn context_with_json_and_interactivity() {
         let ctx = Context::new()
@@ -201,4 +516,16 @@ mod tests {
         assert!(!ctx.is_interactive());
         assert_eq!(ctx.json_data().unwrap()["foo"], "bar");
     }
+
+    #[test]
+    fn context_with_data_merges_sources() {
+        let ctx = Context::new()
+            .with_data(serde_json::json!({ "db": { "host": "h", "port": 1 } }))
+            .with_data(serde_json::json!({ "db": { "port": 2 } }));
+
+        assert_eq!(
+            ctx.json_data().unwrap(),
+            &serde_json::json!({ "db": { "host": "h", "port": 2 } })
+        );
+    }
 }
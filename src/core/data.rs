@@ -0,0 +1,249 @@
+//! Loading `{{$.path}}` data from JSON, YAML, TOML and `.env` files.
+//!
+//! Existing project files such as `Cargo.toml`, `package.json` or
+//! `docker-compose.yml` can drive a template without being converted first:
+//!
+//! ```rust
+//! use spark::data::{self, DataFormat};
+//!
+//! let mut value = data::parse("[package]\nname = \"spark\"", DataFormat::Toml)?;
+//! data::merge(&mut value, data::parse("package:\n  edition: '2021'", DataFormat::Yaml)?);
+//!
+//! assert_eq!(value["package"]["name"], "spark");
+//! assert_eq!(value["package"]["edition"], "2021");
+//! # Ok::<(), spark::Error>(())
+//! ```
+
+use crate::{Error, Result};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+/// File formats data can be loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    /// `.json`
+    Json,
+    /// `.yaml` / `.yml`
+    Yaml,
+    /// `.toml`
+    Toml,
+    /// `KEY=VALUE` lines: `.env`, `.env.local`, `prod.env`. Values are strings.
+    Env,
+}
+
+impl DataFormat {
+    /// Format of a file judged by its name, or `None` if the name does not tell.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name == ".env" || name.starts_with(".env.") {
+            return Some(Self::Env);
+        }
+        match name.rsplit_once('.')?.1 {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "env" => Some(Self::Env),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of `content`, for stdin and files without a known
+    /// extension: JSON, then TOML, then YAML if it holds a mapping or a list,
+    /// and `.env` otherwise.
+    pub fn detect(content: &str) -> Self {
+        if serde_json::from_str::<Value>(content).is_ok() {
+            Self::Json
+        } else if content.parse::<toml::Value>().is_ok() {
+            Self::Toml
+        } else if matches!(
+            serde_yaml::from_str::<Value>(content),
+            Ok(Value::Object(_) | Value::Array(_))
+        ) {
+            Self::Yaml
+        } else {
+            Self::Env
+        }
+    }
+}
+
+/// Parses `content` in the given format into a JSON value.
+pub fn parse(content: &str, format: DataFormat) -> Result<Value> {
+    let value = match format {
+        DataFormat::Json => serde_json::from_str(content)?,
+        DataFormat::Yaml => {
+            serde_yaml::from_str(content).map_err(|e| Error::Custom(e.to_string()))?
+        }
+        DataFormat::Toml => toml_to_json(content.parse::<toml::Value>()?),
+        DataFormat::Env => parse_env(content)?,
+    };
+    Ok(value)
+}
+
+/// `KEY=VALUE` lines with optional `export`, `#` comments and single or double
+/// quotes; `\n`, `\"` and `\\` are unescaped inside double quotes.
+fn parse_env(content: &str) -> Result<Value> {
+    let mut map = Map::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .filter(|(key, _)| !key.trim().is_empty())
+            .ok_or_else(|| Error::Custom(format!("line {}: expected KEY=VALUE", index + 1)))?;
+
+        let value = value.trim();
+        let value = if let Some(inner) = quoted(value, '"') {
+            inner
+                .replace("\\n", "\n")
+                .replace("\\\"", "\"")
+                .replace("\\\\", "\\")
+        } else if let Some(inner) = quoted(value, '\'') {
+            inner.to_string()
+        } else {
+            // Unquoted values end at an inline comment.
+            value
+                .split(" #")
+                .next()
+                .unwrap_or_default()
+                .trim_end()
+                .to_string()
+        };
+        map.insert(key.trim().to_string(), Value::String(value));
+    }
+    Ok(Value::Object(map))
+}
+
+fn quoted(value: &str, quote: char) -> Option<&str> {
+    value.strip_prefix(quote)?.strip_suffix(quote)
+}
+
+/// Reads and parses a data file, or stdin when `path` is `-`. The format is
+/// taken from the file name and [detected](DataFormat::detect) otherwise.
+///
+/// # Errors
+/// Returns [`Error::Data`] naming the file if it cannot be read or parsed.
+pub fn load(path: &str) -> Result<Value> {
+    let wrap = |message: String| Error::Data {
+        path: path.to_string(),
+        message,
+    };
+
+    let content = if path == "-" {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| wrap(e.to_string()))?;
+        content
+    } else {
+        fs::read_to_string(path).map_err(|e| wrap(e.to_string()))?
+    };
+
+    let format =
+        DataFormat::from_path(Path::new(path)).unwrap_or_else(|| DataFormat::detect(&content));
+    parse(&content, format).map_err(|e| wrap(e.to_string()))
+}
+
+/// Deep-merges `overlay` into `base`: objects are merged key by key, any other
+/// value in `overlay` (arrays included) replaces the one in `base`.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_each_format() {
+        assert_eq!(
+            parse(r#"{"a": {"b": 1}}"#, DataFormat::Json).unwrap(),
+            json!({ "a": { "b": 1 } })
+        );
+        assert_eq!(
+            parse("a:\n  b: [1, two]\n", DataFormat::Yaml).unwrap(),
+            json!({ "a": { "b": [1, "two"] } })
+        );
+        assert_eq!(
+            parse("[a]\nb = 1\nwhen = 1979-05-27\n", DataFormat::Toml).unwrap(),
+            json!({ "a": { "b": 1, "when": "1979-05-27" } })
+        );
+        assert_eq!(
+            parse(
+                "# comment\nA=1 # one\nexport B=\"x\\ny\"\nC='$HOME'\n",
+                DataFormat::Env
+            )
+            .unwrap(),
+            json!({ "A": "1", "B": "x\ny", "C": "$HOME" })
+        );
+    }
+
+    #[test]
+    fn format_comes_from_name_or_content() {
+        let format = |name: &str| DataFormat::from_path(Path::new(name));
+        assert_eq!(format("Cargo.toml"), Some(DataFormat::Toml));
+        assert_eq!(format("docker-compose.yml"), Some(DataFormat::Yaml));
+        assert_eq!(format(".env.local"), Some(DataFormat::Env));
+        assert_eq!(format("data"), None);
+
+        assert_eq!(DataFormat::detect("[1, 2]"), DataFormat::Json);
+        assert_eq!(DataFormat::detect("a = 'b'"), DataFormat::Toml);
+        assert_eq!(DataFormat::detect("a: b"), DataFormat::Yaml);
+        assert_eq!(DataFormat::detect("A=b c"), DataFormat::Env);
+    }
+
+    #[test]
+    fn merge_is_deep_and_later_values_win() {
+        let mut base = json!({ "db": { "host": "localhost", "port": 5432 }, "tags": [1] });
+        merge(
+            &mut base,
+            json!({ "db": { "port": 6432 }, "tags": [2], "name": "x" }),
+        );
+        assert_eq!(
+            base,
+            json!({ "db": { "host": "localhost", "port": 6432 }, "tags": [2], "name": "x" })
+        );
+    }
+
+    #[test]
+    fn load_names_the_file_on_error() {
+        let err = load("/nonexistent/data.yaml").unwrap_err();
+        assert!(matches!(err, Error::Data { ref path, .. } if path == "/nonexistent/data.yaml"));
+    }
+}
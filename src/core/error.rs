@@ -1,3 +1,4 @@
+use crate::templates::strict::UnknownKey;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -12,6 +13,10 @@ pub enum Error {
     #[error("Failed to parse TOML template: {0}")]
     TomlDe(#[from] toml::de::Error),
 
+    /// Template TOML contains keys Spark does not understand (strict parsing only).
+    #[error("Unknown keys in template:{}", format_unknown_keys(.0))]
+    UnknownKeys(Vec<UnknownKey>),
+
     /// Failed to serialize a template structure into TOML.
     #[error("Failed to serialize TOML template: {0}")]
     TomlSer(#[from] toml::ser::Error),
@@ -28,14 +33,33 @@ pub enum Error {
     #[error("Liquid template error: {0}")]
     Liquid(#[from] liquid::Error),
 
+    /// A `--data` style data file could not be read or parsed.
+    #[error("Failed to load data from '{path}': {message}")]
+    Data {
+        /// File the data was read from, `-` for stdin.
+        path: String,
+        /// Description of the error.
+        message: String,
+    },
+
     /// `jaq` JSON path filter evaluation error.
     #[error("JSON filter error: {0}")]
     JsonFilter(String),
 
-    /// Required variable or project name was missing in non-interactive mode.
+    /// Required variable or project name was missing in non-interactive or
+    /// [strict](crate::Context::strict) mode.
     #[error("Missing required variable '{0}'")]
     MissingVariable(String),
 
+    /// Several variables were missing; all of them, in the order they appear in
+    /// the template (see [`crate::Template::plan`]).
+    #[error("Missing required variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+
+    /// Placeholder referenced a function that is neither built in nor registered.
+    #[error("Unknown function '{0}'")]
+    UnknownFunction(String),
+
     /// Interactive terminal prompt failed.
     #[error("Interactive prompt failed: {0}")]
     Prompt(String),
@@ -49,6 +73,21 @@ pub enum Error {
         message: String,
     },
 
+    /// A rendered file path resolves outside the output directory of an untrusted
+    /// template (see [`crate::Context::trusted`]).
+    #[error("Output path '{path}' resolves outside of {root:?}")]
+    PathEscape {
+        /// Rendered path as written in the template.
+        path: String,
+        /// Directory output is confined to.
+        root: PathBuf,
+    },
+
+    /// A post-generation step such as `git init` failed. Files written by the
+    /// extraction have been rolled back.
+    #[error("Post-generation step failed: {0}")]
+    Hook(String),
+
     /// Specified template path does not exist or is invalid.
     #[error("Invalid template path: {0:?}")]
     InvalidPath(PathBuf),
@@ -58,6 +97,52 @@ pub enum Error {
     Custom(String),
 }
 
+impl Error {
+    /// Name of the variant, e.g. `"MissingVariable"`, for tools that report
+    /// errors in a structured form.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "Io",
+            Self::TomlDe(_) => "TomlDe",
+            Self::UnknownKeys(_) => "UnknownKeys",
+            Self::TomlSer(_) => "TomlSer",
+            Self::Json(_) => "Json",
+            Self::Regex(_) => "Regex",
+            Self::Liquid(_) => "Liquid",
+            Self::Data { .. } => "Data",
+            Self::JsonFilter(_) => "JsonFilter",
+            Self::MissingVariable(_) => "MissingVariable",
+            Self::MissingVariables(_) => "MissingVariables",
+            Self::UnknownFunction(_) => "UnknownFunction",
+            Self::Prompt(_) => "Prompt",
+            Self::OutputWrite { .. } => "OutputWrite",
+            Self::PathEscape { .. } => "PathEscape",
+            Self::Hook(_) => "Hook",
+            Self::InvalidPath(_) => "InvalidPath",
+            Self::Custom(_) => "Custom",
+        }
+    }
+
+    /// [`Error::MissingVariable`] for a single name, [`Error::MissingVariables`]
+    /// for several, without duplicates.
+    pub(crate) fn missing_variables(names: Vec<String>) -> Self {
+        let mut unique: Vec<String> = Vec::with_capacity(names.len());
+        for name in names {
+            if !unique.contains(&name) {
+                unique.push(name);
+            }
+        }
+        match <[String; 1]>::try_from(unique) {
+            Ok([name]) => Self::MissingVariable(name),
+            Err(names) => Self::MissingVariables(names),
+        }
+    }
+}
+
+fn format_unknown_keys(keys: &[UnknownKey]) -> String {
+    keys.iter().map(|key| format!("\n  {}", key)).collect()
+}
+
 /// Convenience type alias for `Result<T, spark::Error>`.
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -0,0 +1,281 @@
+//! Progress events and the [`Reporter`]s that receive them.
+//!
+//! Everything worth telling the user, from files written to variables without a
+//! value, is sent as an [`Event`] to the context's reporter:
+//!
+//! - [`SilentReporter`], the default, drops them.
+//! - [`TerminalReporter`] prints them the way the `spark` CLI does.
+//! - Any `Fn(&Event)` closure can act as a reporter, e.g. to print JSON lines or
+//!   drive an editor integration.
+//!
+//! The library only writes to the terminal itself to ask questions when the
+//! context is interactive, in the deprecated
+//! [`Template::show_info`](crate::Template::show_info), and in the legacy
+//! [`Template::extract`](crate::Template::extract), which reports to a
+//! [`TerminalReporter`].
+//!
+//! ```rust
+//! use spark::{events::Event, Context, File, Template};
+//! use std::sync::{Arc, Mutex};
+//!
+//! let written = Arc::new(Mutex::new(Vec::new()));
+//! let sink = written.clone();
+//! let ctx = Context::new()
+//!     .with_reporter(move |event: &Event| {
+//!         if let Event::FileWritten { path, .. } = event {
+//!             sink.lock().unwrap().push(path.clone());
+//!         }
+//!     })
+//!     .non_interactive();
+//!
+//! Template::builder()
+//!     .with_file(File::create("stdout://", "hello\n"))
+//!     .extract_with_context(&ctx)?;
+//! assert_eq!(*written.lock().unwrap(), ["stdout://"]);
+//! # Ok::<(), spark::Error>(())
+//! ```
+
+use crate::{Keywords, OutputTarget};
+use colored::*;
+use serde::Serialize;
+use std::{fmt, sync::Arc};
+
+/// Something that happened during an extraction. Serializes as an object with
+/// an `"event"` tag, e.g. `{"event":"file_written","path":"README.md",...}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Extraction of a template started.
+    TemplateLoaded {
+        name: Option<String>,
+        description: Option<String>,
+        author: Option<String>,
+        /// File the template was loaded from.
+        source: Option<String>,
+    },
+    /// The following questions belong to a `group` from `[variables]`.
+    PromptGroup { name: String },
+    /// The user is being asked for the value of a variable. `help` is the
+    /// variable's help text from `[variables]`.
+    VariablePrompted { name: String, help: Option<String> },
+    /// The user answered a prompt. `value` is `None` for secrets, as in
+    /// [`Event::VariableResolved`].
+    VariableAnswered { name: String, value: Option<String> },
+    /// A placeholder has no value and no way to get one; it renders empty.
+    VariableMissing { name: String },
+    /// A variable the template uses got its value. `value` is `None` for
+    /// variables that look like secrets (see [`crate::templates::manifest::is_secret`]).
+    VariableResolved { name: String, value: Option<String> },
+    /// Rendered output was written.
+    FileWritten {
+        /// Path as rendered from the template.
+        path: String,
+        /// `file`, `stdout`, `stderr` or `clipboard`.
+        target: String,
+        /// Where a `file` target ended up after expansion and `--output-dir`.
+        location: Option<String>,
+        /// Size of the written content in bytes.
+        bytes: usize,
+    },
+    /// A post-generation step such as `git init` is about to run in `dir`.
+    HookStarted { hook: String, dir: String },
+    /// A post-generation step completed.
+    HookFinished {
+        hook: String,
+        dir: String,
+        message: String,
+    },
+    /// Something the user should know, e.g. that the config is being created.
+    Info { message: String },
+    /// Something was off but the extraction carried on, e.g. a failed JSON lookup.
+    Warning { message: String },
+    /// The extraction failed. `kind` is the [`crate::Error`] variant name.
+    Error { kind: String, message: String },
+}
+
+impl Event {
+    pub(crate) fn file_written(path: &str, target: &OutputTarget, content: &str) -> Self {
+        let kind = match target {
+            OutputTarget::File(_) => "file",
+            OutputTarget::Stdout => "stdout",
+            OutputTarget::Stderr => "stderr",
+            OutputTarget::Clipboard => "clipboard",
+        };
+        Self::FileWritten {
+            path: path.to_string(),
+            target: kind.to_string(),
+            location: target
+                .expanded_path()
+                .map(|path| path.to_string_lossy().to_string()),
+            bytes: content.len(),
+        }
+    }
+
+    pub(crate) fn answered(name: &str, value: &str) -> Self {
+        Self::VariableAnswered {
+            name: name.to_string(),
+            value: (!crate::templates::manifest::is_secret(name)).then(|| value.to_string()),
+        }
+    }
+
+    pub(crate) fn error(error: &crate::Error) -> Self {
+        Self::Error {
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Receives the [`Event`]s of an extraction. Set with
+/// [`crate::Context::with_reporter`].
+pub trait Reporter: Send + Sync {
+    fn report(&self, event: &Event);
+}
+
+impl<F> Reporter for F
+where
+    F: Fn(&Event) + Send + Sync,
+{
+    fn report(&self, event: &Event) {
+        self(event)
+    }
+}
+
+impl<R: Reporter + ?Sized> Reporter for Arc<R> {
+    fn report(&self, event: &Event) {
+        (**self).report(event)
+    }
+}
+
+/// Drops every event. The default for a [`crate::Context`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn report(&self, _event: &Event) {}
+}
+
+/// Prints events as colored text, the way the `spark` CLI reports progress.
+/// Template info, resolved variables and answers are not printed; of a prompt,
+/// only its group heading and help text are, before the question itself.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalReporter;
+
+impl Reporter for TerminalReporter {
+    fn report(&self, event: &Event) {
+        match event {
+            Event::FileWritten {
+                target, location, ..
+            } => match (target.as_str(), location) {
+                ("file", Some(location)) => {
+                    println!("{}: {}", "file written".blue(), location.bold().green())
+                }
+                ("clipboard", _) => println!("{}", "copied to clipboard".blue()),
+                _ => {}
+            },
+            Event::HookStarted { hook, dir } => {
+                println!("\n{} {} in {}\n", "Running".blue(), hook.bold(), dir.blue())
+            }
+            Event::HookFinished { message, .. } => {
+                println!("{}", format!("\n✅ {}", message).green().bold())
+            }
+            Event::PromptGroup { name } => println!("\n{}", name.bold().underline()),
+            Event::VariablePrompted {
+                help: Some(help), ..
+            } => println!("{}", help.dimmed()),
+            Event::VariableMissing { name } => eprintln!(
+                "\n[{}] {}: {}",
+                "WRN".yellow(),
+                "Value not found".yellow(),
+                Keywords::from(name, None).green()
+            ),
+            Event::Info { message } => eprintln!("\n[{}] {}", "INFO".bold().blue(), message),
+            Event::Warning { message } => eprintln!("\n[{}] {}", "WRN".yellow(), message),
+            Event::Error { message, .. } => eprintln!("{}: {}", "error".red().bold(), message),
+            Event::TemplateLoaded { .. }
+            | Event::VariableResolved { .. }
+            | Event::VariablePrompted { .. }
+            | Event::VariableAnswered { .. } => {}
+        }
+    }
+}
+
+/// The reporter a context sends its events to.
+#[derive(Clone)]
+pub(crate) struct Events(Arc<dyn Reporter>);
+
+impl Events {
+    pub(crate) fn new(reporter: impl Reporter + 'static) -> Self {
+        Self(Arc::new(reporter))
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        self.0.report(&event);
+    }
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self::new(SilentReporter)
+    }
+}
+
+impl fmt::Debug for Events {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Events")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn events_serialize_with_a_tag() {
+        let event = Event::file_written(
+            "src/main.rs",
+            &OutputTarget::File(PathBuf::from("out/src/main.rs")),
+            "fn main() {}",
+        );
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "event": "file_written",
+                "path": "src/main.rs",
+                "target": "file",
+                "location": "out/src/main.rs",
+                "bytes": 12
+            })
+        );
+    }
+
+    #[test]
+    fn context_reports_missing_variables_to_its_reporter() {
+        use crate::{Context, File, Template};
+        use std::sync::Mutex;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let context = Context::new()
+            .with_reporter(move |event: &Event| sink.lock().unwrap().push(event.clone()))
+            .non_interactive();
+
+        Template::builder()
+            .with_file(File::create("out.txt", "{{$MISSING}}"))
+            .render(&context)
+            .unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            [Event::VariableMissing {
+                name: "MISSING".to_string()
+            }]
+        );
+
+        // The default reporter drops events instead of printing them.
+        Template::builder()
+            .with_file(File::create("out.txt", "{{$MISSING}}"))
+            .render(&Context::new().non_interactive())
+            .unwrap();
+    }
+}
@@ -1,30 +1,215 @@
+use crate::events::Event;
+use crate::Context;
 use crate::Fns;
 use crate::Keywords;
-use colored::*;
+use chrono::format::{Item, StrftimeItems};
 use indexmap::IndexMap;
 use promptly::prompt;
+use rand::{distributions::Alphanumeric, Rng};
 use regex::Regex;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+/// Length used by `:random` when no length is given.
+pub const DEFAULT_RANDOM_LENGTH: usize = 32;
+/// Longest string `:random(N)` generates; larger lengths do not parse.
+pub const MAX_RANDOM_LENGTH: usize = 4096;
+
+/// Signature of a custom placeholder function.
+///
+/// The first argument is the variable name (`X` in `{{$X:myfunc}}`), the second
+/// is the context the template is being rendered with.
+pub type Function = dyn Fn(&str, &Context) -> crate::Result<String> + Send + Sync;
+
+/// Named placeholder functions usable as `{{$X:name}}` in addition to the
+/// built-in `read`.
+///
+/// # Examples
+/// ```rust
+/// use spark::{Context, File, Template};
+///
+/// let ctx = Context::new()
+///     .with_function("shout", |name, _ctx| Ok(name.to_uppercase()))
+///     .non_interactive();
+///
+/// let template = Template::builder().with_file(File::create("out.txt", "{{$hello:shout}}"));
+/// let rendered = template.render(&ctx)?;
+/// assert_eq!(rendered[0].content, "HELLO");
+/// # Ok::<(), spark::Error>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, Arc<Function>>,
+}
+
+impl FunctionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `function` under `name`, replacing any previous function with
+    /// the same name. Built-in function names (such as `read`) always take
+    /// precedence over registered ones.
+    pub fn register<F>(&mut self, name: impl Into<String>, function: F)
+    where
+        F: Fn(&str, &Context) -> crate::Result<String> + Send + Sync + 'static,
+    {
+        self.functions.insert(name.into(), Arc::new(function));
+    }
+
+    /// Checks whether a function is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Returns the names of all registered functions.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
+
+    /// Invokes the function registered under `name` for the variable `keyword_name`.
+    pub fn call(&self, name: &str, keyword_name: &str, context: &Context) -> crate::Result<String> {
+        let function = self
+            .functions
+            .get(name)
+            .ok_or_else(|| crate::Error::UnknownFunction(name.to_string()))?;
+        function(keyword_name, context)
+    }
+}
+
+impl fmt::Debug for FunctionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&str> = self.names().collect();
+        names.sort_unstable();
+        f.debug_struct("FunctionRegistry")
+            .field("functions", &names)
+            .finish()
+    }
+}
 
 impl std::fmt::Display for Fns {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Read => write!(f, "read"),
+            Self::Call(name) => write!(f, "{}", name),
+            Self::Uuid => write!(f, "uuid"),
+            Self::Random(len) => write!(f, "random({})", len),
+            Self::RandInt(min, max) => write!(f, "randint({},{})", min, max),
+            Self::Date(format) => write!(f, "date({})", format),
+            Self::Sha256 => write!(f, "sha256"),
             Self::None => write!(f, ""),
         }
     }
 }
 
+/// Hex encoded SHA-256 digest of `data`.
+pub(crate) fn sha256_hex(data: impl AsRef<[u8]>) -> String {
+    Sha256::digest(data.as_ref())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
 impl Fns {
     pub fn remove_fn_name(keyword: &str, func_name: Self) -> String {
         keyword.replace(&format!(":{}", func_name), "")
     }
 
+    /// Parses the function part of a placeholder (`read`, `random(32)`, ...),
+    /// falling back to functions registered in `functions`.
+    pub fn parse(spec: &str, functions: &FunctionRegistry) -> Option<Self> {
+        let (name, args) = match spec.split_once('(') {
+            Some((name, rest)) => (name.trim(), Some(rest.strip_suffix(')')?.trim())),
+            None => (spec.trim(), None),
+        };
+
+        let func = match (name, args) {
+            ("read", None) => Self::Read,
+            ("uuid", None) => Self::Uuid,
+            ("sha256", None) => Self::Sha256,
+            ("random", None) => Self::Random(DEFAULT_RANDOM_LENGTH),
+            ("random", Some(len)) => match len.parse().ok()? {
+                len if len <= MAX_RANDOM_LENGTH => Self::Random(len),
+                _ => return None,
+            },
+            ("randint", Some(range)) => {
+                let (min, max) = range.split_once(',')?;
+                let (min, max) = (min.trim().parse().ok()?, max.trim().parse().ok()?);
+                if min > max {
+                    return None;
+                }
+                Self::RandInt(min, max)
+            }
+            ("date", Some(format)) => {
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    return None;
+                }
+                Self::Date(format.to_string())
+            }
+            (name, None) if functions.contains(name) => Self::Call(name.to_string()),
+            _ => return None,
+        };
+        Some(func)
+    }
+
+    /// Whether the function derives its value from the variable it is attached
+    /// to instead of producing a value for it.
+    pub fn is_transform(&self) -> bool {
+        matches!(self, Self::Sha256)
+    }
+
+    /// Produces the value of a generator function (`uuid`, `random`, `randint`, `date`).
+    ///
+    /// Randomness comes from [`Context::rng_for`], so a seeded context yields the same
+    /// value for the same variable on every render; `date` reads [`Context::now`].
+    pub fn generate(&self, keyword_name: &str, context: &Context) -> crate::Result<String> {
+        let mut rng = context.rng_for(&format!("{}:{}", keyword_name, self));
+        match self {
+            Self::Uuid => Ok(uuid::Builder::from_random_bytes(rng.gen())
+                .into_uuid()
+                .to_string()),
+            Self::Random(len) => Ok((0..*len)
+                .map(|_| rng.sample(Alphanumeric) as char)
+                .collect()),
+            Self::RandInt(min, max) => Ok(rng.gen_range(*min..=*max).to_string()),
+            Self::Date(format) => Ok(context.now().format(format).to_string()),
+            other => Err(crate::Error::Custom(format!(
+                "'{}' is not a generator function",
+                other
+            ))),
+        }
+    }
+
+    /// Placeholders in `txt` not yet in `keywords`, keyed by variable name.
+    /// Returns `None` if one uses a function that is not built in; see
+    /// [`Fns::find_with`] to accept registered functions too.
     pub fn find(
         txt: &str,
         keywords: &HashMap<String, String>,
         re: &Regex,
     ) -> Option<IndexMap<String, (String, Self)>> {
+        Self::find_with(txt, keywords, re, &FunctionRegistry::default())
+    }
+
+    /// [`Fns::find`] also accepting the functions registered in `functions`.
+    pub fn find_with(
+        txt: &str,
+        keywords: &HashMap<String, String>,
+        re: &Regex,
+        functions: &FunctionRegistry,
+    ) -> Option<IndexMap<String, (String, Self)>> {
+        Self::find_checked(txt, keywords, re, functions).ok()
+    }
+
+    /// Like [`Fns::find`], but reports a placeholder using an unknown function as
+    /// [`crate::Error::UnknownFunction`] instead of returning `None`.
+    pub fn find_checked(
+        txt: &str,
+        keywords: &HashMap<String, String>,
+        re: &Regex,
+        functions: &FunctionRegistry,
+    ) -> Result<IndexMap<String, (String, Self)>, crate::Error> {
         let mut found = IndexMap::new();
         for cap in re.captures_iter(txt) {
             if let Some(key_match) = cap.get(0) {
@@ -33,32 +218,38 @@ impl Fns {
                 // if lhs function is the same as rhs function then no need to override in the IndexMap
                 if !keywords.contains_key(&keyword) {
                     let stripped_keyword = Keywords::strip(&keyword);
-                    let parts: Vec<&str> = stripped_keyword.split(':').collect();
-                    if parts.len() == 2 {
-                        let parsed_func = match parts[1].trim() {
-                            "read" => Self::Read,
-                            _ => {
-                                eprintln!(
-                                    "\n{}: '{}' is not a valid function",
-                                    "error".red(),
-                                    parts[1].yellow()
-                                );
-                                return None;
-                            }
+                    // JSON paths (`{{$.a.b}}`) may legitimately contain ':'
+                    let split = if stripped_keyword.starts_with('.') {
+                        None
+                    } else {
+                        stripped_keyword.split_once(':')
+                    };
+
+                    if let Some((name, spec)) = split {
+                        let Some(parsed_func) = Self::parse(spec, functions) else {
+                            return Err(crate::Error::UnknownFunction(spec.to_string()));
                         };
 
-                        if let Some((_key, val)) = found.get(parts[0]) {
-                            match (val, parsed_func) {
+                        // transforms depend on the variable's value, so they are tracked
+                        // separately from whatever produces that value
+                        let key = if parsed_func.is_transform() {
+                            stripped_keyword.clone()
+                        } else {
+                            name.to_string()
+                        };
+
+                        if let Some((_key, val)) = found.get(&key) {
+                            match (val, &parsed_func) {
                                 (&Fns::None, _) => {
-                                    found.insert(parts[0].to_string(), (keyword, parsed_func));
+                                    found.insert(key, (keyword, parsed_func));
                                 }
                                 _ => continue,
                             }
                         } else {
-                            found.insert(parts[0].to_string(), (keyword, parsed_func));
+                            found.insert(key, (keyword, parsed_func));
                         }
                     } else {
-                        if found.contains_key(parts[0]) {
+                        if found.contains_key(&stripped_keyword) {
                             continue;
                         }
                         found.insert(stripped_keyword.clone(), (keyword, Self::None));
@@ -66,70 +257,74 @@ impl Fns {
                 }
             }
         }
-        Some(found)
+        Ok(found)
     }
 
     pub fn exec(func: Self, keyword_name: &str) -> Result<String, String> {
         match func {
             Self::Read => prompt(keyword_name).map_err(|_| "Failed to read input".to_string()),
+            Self::Call(name) => Err(format!(
+                "function '{}' needs a context, use Fns::find_and_resolve_with",
+                name
+            )),
+            Self::Sha256 => Ok(sha256_hex(keyword_name)),
             Self::None => Ok(keyword_name.to_string()),
+            generator => generator
+                .generate(keyword_name, &Context::new())
+                .map_err(|e| e.to_string()),
         }
     }
 
+    /// Legacy resolution of the placeholders in `txt` into `keywords`: failed
+    /// lookups and placeholders without a value render empty and are reported
+    /// to the context's reporter. Prefer [`Fns::find_and_resolve_with`].
+    ///
+    /// Each placeholder goes through the same resolution as
+    /// [`Fns::find_and_resolve_with`], one at a time so a failure only blanks the
+    /// placeholder it happened in.
     pub fn find_and_exec(
         txt: &str,
         keywords: &mut HashMap<String, String>,
         re: &Regex,
         json_data: &serde_json::Value,
+        context: &Context,
     ) {
-        if let Some(found) = Self::find(txt, keywords, re) {
-            for (keyword_name, (keyword, function)) in found {
-                let final_keyword = Self::remove_fn_name(&keyword, function);
-                if keywords.contains_key(&final_keyword) {
-                    keywords.insert(keyword, keywords[&final_keyword].clone());
-                    continue;
-                }
-
-                if !json_data.is_null() && keyword_name.contains('.') {
-                    match Self::eval_json_filter(&keyword_name, json_data) {
-                        Ok(resolved) => {
-                            keywords.insert(keyword, resolved);
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "\n[{}] {}: {} ({})",
-                                "WRN".yellow(),
-                                "jq lookup failed".yellow(),
-                                keyword_name.green(),
-                                e
-                            );
-                            keywords.insert(keyword, String::new());
-                        }
-                    }
-                    continue;
-                }
-
-                match function {
-                    Self::Read => {
-                        let value: String =
-                            prompt(&keyword_name).unwrap_or_default();
-                        keywords.insert(keyword.clone(), value.clone());
-                        keywords.insert(final_keyword, value);
-                    }
-                    Self::None => {
-                        eprintln!(
-                            "\n[{}] {}: {}",
-                            "WRN".yellow(),
-                            "Value not found".yellow(),
-                            keyword.green()
-                        );
-                        keywords.insert(keyword, String::new());
-                    }
-                }
+        let is_transform = |placeholder: &str| {
+            Keywords::strip(placeholder)
+                .split_once(':')
+                .and_then(|(_, spec)| Self::parse(spec, &context.functions))
+                .is_some_and(|function| function.is_transform())
+        };
+        let (transforms, others): (Vec<&str>, Vec<&str>) = re
+            .find_iter(txt)
+            .map(|found| found.as_str())
+            .partition(|placeholder| is_transform(placeholder));
+
+        // Transforms run last, as in `find_and_resolve_with`.
+        for placeholder in others.into_iter().chain(transforms) {
+            let mut missing = Vec::new();
+            if let Err(e) = Self::resolve_collecting(
+                placeholder,
+                keywords,
+                re,
+                json_data,
+                context,
+                &mut missing,
+            ) {
+                context.emit(Event::Warning {
+                    message: format!("{}: {}", placeholder, e),
+                });
             }
+            for name in missing {
+                context.emit(Event::VariableMissing { name });
+            }
+            keywords.entry(placeholder.to_string()).or_default();
         }
     }
 
+    /// Resolves the placeholders in `txt` into `keywords`, prompting for
+    /// missing values if `interactive` is set.
+    #[deprecated(note = "use `Fns::find_and_resolve_with` and a `Context`")]
     pub fn find_and_resolve(
         txt: &str,
         keywords: &mut HashMap<String, String>,
@@ -137,51 +332,131 @@ impl Fns {
         json_data: &serde_json::Value,
         interactive: bool,
     ) -> Result<(), crate::Error> {
-        if let Some(found) = Self::find(txt, keywords, re) {
-            for (keyword_name, (keyword, function)) in found {
-                let final_keyword = Self::remove_fn_name(&keyword, function);
-                if keywords.contains_key(&final_keyword) {
-                    keywords.insert(keyword, keywords[&final_keyword].clone());
-                    continue;
-                }
+        let context = Context::new().with_interactive(interactive);
+        Self::find_and_resolve_with(txt, keywords, re, json_data, &context)
+    }
 
-                if !json_data.is_null() && keyword_name.contains('.') {
-                    match Self::eval_json_filter(&keyword_name, json_data) {
-                        Ok(resolved) => {
-                            keywords.insert(keyword, resolved);
-                        }
-                        Err(e) => {
-                            return Err(crate::Error::JsonFilter(format!(
-                                "{}: {}",
-                                keyword_name, e
-                            )));
-                        }
+    /// Resolves the placeholders in `txt` into `keywords`.
+    ///
+    /// Placeholders without a value render empty, unless the context is
+    /// [strict](Context::strict) or cannot prompt: then every missing name is
+    /// returned in one [`crate::Error::MissingVariables`].
+    pub fn find_and_resolve_with(
+        txt: &str,
+        keywords: &mut HashMap<String, String>,
+        re: &Regex,
+        json_data: &serde_json::Value,
+        context: &Context,
+    ) -> Result<(), crate::Error> {
+        let mut missing = Vec::new();
+        Self::resolve_collecting(txt, keywords, re, json_data, context, &mut missing)?;
+        match missing.is_empty() {
+            true => Ok(()),
+            false => Err(crate::Error::missing_variables(missing)),
+        }
+    }
+
+    /// [`Fns::find_and_resolve_with`] adding the names that could not be resolved to
+    /// `missing` instead of failing on the first, so a whole template can be
+    /// checked at once.
+    pub(crate) fn resolve_collecting(
+        txt: &str,
+        keywords: &mut HashMap<String, String>,
+        re: &Regex,
+        json_data: &serde_json::Value,
+        context: &Context,
+        missing: &mut Vec<String>,
+    ) -> Result<(), crate::Error> {
+        let found = Self::find_checked(txt, keywords, re, &context.functions)?;
+        let mut transforms = Vec::new();
+        for (keyword_name, (keyword, function)) in found {
+            if function.is_transform() {
+                transforms.push((keyword_name, keyword, function));
+                continue;
+            }
+
+            let final_keyword = Keywords::from(&keyword_name, None);
+            if keywords.contains_key(&final_keyword) {
+                keywords.insert(keyword, keywords[&final_keyword].clone());
+                continue;
+            }
+
+            if !json_data.is_null() && keyword_name.contains('.') {
+                match Self::eval_json_lookup(&keyword_name, json_data) {
+                    Ok(Some(resolved)) => {
+                        keywords.insert(keyword, resolved);
+                    }
+                    Ok(None) | Err(_) if context.strict => missing.push(keyword_name),
+                    Ok(None) => {
+                        keywords.insert(keyword, "null".to_string());
+                    }
+                    Err(e) => {
+                        return Err(crate::Error::JsonFilter(format!("{}: {}", keyword_name, e)));
                     }
-                    continue;
                 }
+                continue;
+            }
 
-                match function {
-                    Self::Read => {
-                        if !interactive {
-                            return Err(crate::Error::MissingVariable(keyword_name));
-                        }
-                        let value: String = prompt(&keyword_name)
-                            .map_err(|e| crate::Error::Prompt(e.to_string()))?;
-                        keywords.insert(keyword.clone(), value.clone());
-                        keywords.insert(final_keyword, value);
-                    }
-                    Self::None => {
-                        eprintln!(
-                            "\n[{}] {}: {}",
-                            "WRN".yellow(),
-                            "Value not found".yellow(),
-                            keyword.green()
-                        );
-                        keywords.insert(keyword, String::new());
+            match function {
+                Self::Read => {
+                    if !context.interactive {
+                        missing.push(keyword_name);
+                        continue;
                     }
+                    context.emit(Event::VariablePrompted {
+                        name: keyword_name.clone(),
+                        help: None,
+                    });
+                    let value: String =
+                        prompt(&keyword_name).map_err(|e| crate::Error::Prompt(e.to_string()))?;
+                    context.emit(Event::answered(&keyword_name, &value));
+                    keywords.insert(keyword.clone(), value.clone());
+                    keywords.insert(final_keyword, value);
+                }
+                Self::Call(name) => {
+                    let value = context.functions.call(&name, &keyword_name, context)?;
+                    keywords.insert(keyword.clone(), value.clone());
+                    keywords.insert(final_keyword, value);
+                }
+                Self::None if context.strict => missing.push(keyword_name),
+                Self::None => {
+                    context.emit(Event::VariableMissing { name: keyword_name });
+                    keywords.insert(keyword, String::new());
+                }
+                generator => {
+                    let value = generator.generate(&keyword_name, context)?;
+                    keywords.insert(keyword.clone(), value.clone());
+                    keywords.insert(final_keyword, value);
                 }
             }
         }
+
+        // Transforms run last so `{{$X:sha256}}` sees X even when X is
+        // produced by a placeholder further down the text.
+        for (keyword_name, keyword, function) in transforms {
+            let name = keyword_name
+                .split_once(':')
+                .map_or(keyword_name.as_str(), |(name, _)| name);
+            let source = Keywords::from(name, None);
+
+            if !keywords.contains_key(&source) {
+                if !context.interactive {
+                    missing.push(name.to_string());
+                    continue;
+                }
+                context.emit(Event::VariablePrompted {
+                    name: name.to_string(),
+                    help: None,
+                });
+                let value: String =
+                    prompt(name).map_err(|e| crate::Error::Prompt(e.to_string()))?;
+                context.emit(Event::answered(name, &value));
+                keywords.insert(source.clone(), value);
+            }
+
+            let value = Self::exec(function, &keywords[&source]).map_err(crate::Error::Custom)?;
+            keywords.insert(keyword, value);
+        }
         Ok(())
     }
 
@@ -189,6 +464,15 @@ impl Fns {
         filter_str: &str,
         json_data: &serde_json::Value,
     ) -> Result<String, String> {
+        Ok(Self::eval_json_lookup(filter_str, json_data)?.unwrap_or_else(|| "null".to_string()))
+    }
+
+    /// [`Fns::eval_json_filter`] returning `None` when the filter yields `null`,
+    /// e.g. for a key that does not exist.
+    pub(crate) fn eval_json_lookup(
+        filter_str: &str,
+        json_data: &serde_json::Value,
+    ) -> Result<Option<String>, String> {
         use jaq_interpret::FilterT;
 
         let mut defs = jaq_interpret::ParseCtx::new(Vec::new());
@@ -207,7 +491,10 @@ impl Fns {
                 .into_iter()
                 .map(|(e, _span)| e.to_string())
                 .collect();
-            return Err(format!("Filter compilation failed: {}", err_msgs.join(", ")));
+            return Err(format!(
+                "Filter compilation failed: {}",
+                err_msgs.join(", ")
+            ));
         }
         let val = jaq_interpret::Val::from(json_data.clone());
         let inputs = jaq_interpret::RcIter::new(core::iter::empty());
@@ -215,8 +502,9 @@ impl Fns {
         if let Some(item) = out.next() {
             match item {
                 Ok(val) => match val {
-                    jaq_interpret::Val::Str(s) => Ok((*s).clone()),
-                    other => Ok(other.to_string()),
+                    jaq_interpret::Val::Null => Ok(None),
+                    jaq_interpret::Val::Str(s) => Ok(Some((*s).clone())),
+                    other => Ok(Some(other.to_string())),
                 },
                 Err(e) => Err(format!("{}", e)),
             }
@@ -309,6 +597,250 @@ mod tests {
         assert!(found.is_none());
     }
 
+    #[test]
+    #[allow(deprecated)]
+    fn find_and_resolve_keeps_the_interactive_flag_entry_point() {
+        let re = keyword_re();
+        let mut keywords = HashMap::new();
+        keywords.insert("{{$NAME}}".to_string(), "spark".to_string());
+
+        Fns::find_and_resolve(
+            "{{$NAME:read}} {{$ID:uuid}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            false,
+        )
+        .unwrap();
+        assert_eq!(keywords["{{$NAME:read}}"], "spark");
+        assert_eq!(keywords["{{$ID}}"].len(), 36);
+
+        let result = Fns::find_and_resolve(
+            "{{$OTHER:read}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            false,
+        );
+        assert!(matches!(result, Err(crate::Error::MissingVariable(v)) if v == "OTHER"));
+    }
+
+    #[test]
+    fn find_accepts_registered_function() {
+        let re = keyword_re();
+        let mut functions = FunctionRegistry::new();
+        functions.register("vault", |_, _| Ok("s3cr3t".to_string()));
+
+        let found = Fns::find_with("{{$TOKEN:vault}}", &HashMap::new(), &re, &functions).unwrap();
+        let (keyword, func) = found.get("TOKEN").expect("TOKEN should be found");
+        assert_eq!(keyword, "{{$TOKEN:vault}}");
+        assert_eq!(func, &Fns::Call("vault".to_string()));
+    }
+
+    #[test]
+    fn find_and_resolve_calls_registered_function_with_context() {
+        let re = keyword_re();
+        let context = Context::new()
+            .with_var("TICKET_PREFIX", "OPS")
+            .with_function("ticket", |name, ctx| {
+                Ok(format!(
+                    "{}-{}",
+                    ctx.get_var("TICKET_PREFIX").unwrap_or(""),
+                    name
+                ))
+            });
+        let mut keywords = HashMap::new();
+
+        Fns::find_and_resolve_with(
+            "{{$42:ticket}} / {{$42}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            &context,
+        )
+        .unwrap();
+
+        assert_eq!(
+            keywords.get("{{$42:ticket}}").map(String::as_str),
+            Some("OPS-42")
+        );
+        assert_eq!(keywords.get("{{$42}}").map(String::as_str), Some("OPS-42"));
+    }
+
+    #[test]
+    fn find_and_resolve_propagates_function_errors() {
+        let re = keyword_re();
+        let context = Context::new().with_function("vault", |name, _| {
+            Err(format!("no secret named {}", name).into())
+        });
+        let mut keywords = HashMap::new();
+
+        let result = Fns::find_and_resolve_with(
+            "{{$DB_PASSWORD:vault}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            &context,
+        );
+        assert!(
+            matches!(result, Err(crate::Error::Custom(msg)) if msg == "no secret named DB_PASSWORD")
+        );
+    }
+
+    #[test]
+    fn parse_recognises_builtin_generators() {
+        let functions = FunctionRegistry::default();
+        assert_eq!(Fns::parse("uuid", &functions), Some(Fns::Uuid));
+        assert_eq!(Fns::parse("sha256", &functions), Some(Fns::Sha256));
+        assert_eq!(
+            Fns::parse("random", &functions),
+            Some(Fns::Random(DEFAULT_RANDOM_LENGTH))
+        );
+        assert_eq!(Fns::parse("random( 8 )", &functions), Some(Fns::Random(8)));
+        assert_eq!(
+            Fns::parse("randint(8000, 9000)", &functions),
+            Some(Fns::RandInt(8000, 9000))
+        );
+        assert_eq!(
+            Fns::parse("date(%H:%M)", &functions),
+            Some(Fns::Date("%H:%M".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_arguments() {
+        let functions = FunctionRegistry::default();
+        assert_eq!(Fns::parse("random(abc)", &functions), None);
+        assert_eq!(Fns::parse("random(4097)", &functions), None);
+        assert_eq!(
+            Fns::parse("random(4096)", &functions),
+            Some(Fns::Random(MAX_RANDOM_LENGTH))
+        );
+        assert_eq!(Fns::parse("randint(9,1)", &functions), None);
+        assert_eq!(Fns::parse("randint(1)", &functions), None);
+        assert_eq!(Fns::parse("date(%Q)", &functions), None);
+        assert_eq!(Fns::parse("uuid(4)", &functions), None);
+    }
+
+    #[test]
+    fn seeded_generators_are_reproducible() {
+        let re = keyword_re();
+        let txt = "{{$ID:uuid}} {{$SECRET:random(16)}} {{$PORT:randint(8000,9000)}}";
+        let resolve = |seed| {
+            let mut keywords = HashMap::new();
+            let context = Context::new().with_seed(seed);
+            Fns::find_and_resolve_with(txt, &mut keywords, &re, &serde_json::Value::Null, &context)
+                .unwrap();
+            keywords
+        };
+
+        let first = resolve(7);
+        assert_eq!(first, resolve(7));
+        assert_ne!(first["{{$ID}}"], resolve(8)["{{$ID}}"]);
+
+        assert_eq!(first["{{$ID:uuid}}"].len(), 36);
+        assert_eq!(first["{{$SECRET}}"].len(), 16);
+        assert!(first["{{$SECRET}}"]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric()));
+        let port: i64 = first["{{$PORT}}"].parse().unwrap();
+        assert!((8000..=9000).contains(&port));
+    }
+
+    #[test]
+    fn date_generator_formats_current_time() {
+        let re = keyword_re();
+        let mut keywords = HashMap::new();
+        Fns::find_and_resolve_with(
+            "{{$STAMP:date(%Y)}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            &Context::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            keywords["{{$STAMP}}"],
+            chrono::Local::now().format("%Y").to_string()
+        );
+    }
+
+    #[test]
+    fn date_generator_uses_frozen_clock() {
+        let re = keyword_re();
+        let now = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap();
+        let mut keywords = HashMap::new();
+        Fns::find_and_resolve_with(
+            "{{$STAMP:date(%Y-%m-%d %H:%M)}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            &Context::new().with_now(now),
+        )
+        .unwrap();
+
+        assert_eq!(keywords["{{$STAMP}}"], "2026-01-01 00:00");
+    }
+
+    #[test]
+    fn sha256_hashes_the_variable_value() {
+        let re = keyword_re();
+        let context = Context::new().non_interactive();
+        let mut keywords = HashMap::new();
+        keywords.insert("{{$PASSWORD}}".to_string(), "spark".to_string());
+
+        Fns::find_and_resolve_with(
+            "{{$PASSWORD:sha256}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            &context,
+        )
+        .unwrap();
+
+        assert_eq!(
+            keywords["{{$PASSWORD:sha256}}"],
+            "92f473809e5979a7da2b7f52771b3a9e3d7105dcb0f24ae333c5bf279b863d73"
+        );
+        assert_eq!(keywords["{{$PASSWORD}}"], "spark");
+    }
+
+    #[test]
+    fn sha256_sees_values_generated_later_in_the_text() {
+        let re = keyword_re();
+        let context = Context::new().with_seed(1).non_interactive();
+        let mut keywords = HashMap::new();
+
+        Fns::find_and_resolve_with(
+            "{{$TOKEN:sha256}} {{$TOKEN:random(12)}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            &context,
+        )
+        .unwrap();
+
+        assert_eq!(
+            keywords["{{$TOKEN:sha256}}"],
+            sha256_hex(&keywords["{{$TOKEN}}"])
+        );
+    }
+
+    #[test]
+    fn sha256_of_unknown_variable_is_missing_in_non_interactive_mode() {
+        let re = keyword_re();
+        let mut keywords = HashMap::new();
+        let result = Fns::find_and_resolve_with(
+            "{{$PASSWORD:sha256}}",
+            &mut keywords,
+            &re,
+            &serde_json::Value::Null,
+            &Context::new().non_interactive(),
+        );
+        assert!(matches!(result, Err(crate::Error::MissingVariable(v)) if v == "PASSWORD"));
+    }
+
     #[test]
     fn exec_none_returns_keyword_name() {
         let res = Fns::exec(Fns::None, "hello").unwrap();
@@ -326,6 +858,7 @@ mod tests {
             &mut keywords,
             &re,
             &serde_json::Value::Null,
+            &Context::new(),
         );
 
         assert_eq!(
@@ -334,6 +867,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn find_and_exec_uses_the_context_and_keeps_spacing_in_keys() {
+        let re = keyword_re();
+        let txt = "{{$ID:random( 8 )}} {{$ID}} {{$DAY:date(%Y)}} {{$X:nope}} {{$Y:shout}}";
+        let now = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap();
+        let context = Context::new()
+            .with_seed(3)
+            .with_now(now)
+            .with_function("shout", |name, _| Ok(name.to_uppercase()));
+        let resolve = || {
+            let mut keywords = HashMap::new();
+            Fns::find_and_exec(txt, &mut keywords, &re, &serde_json::Value::Null, &context);
+            keywords
+        };
+
+        let keywords = resolve();
+        assert_eq!(keywords["{{$ID:random( 8 )}}"].len(), 8);
+        assert_eq!(keywords["{{$ID:random( 8 )}}"], keywords["{{$ID}}"]);
+        assert_eq!(keywords, resolve());
+        assert_eq!(keywords["{{$DAY:date(%Y)}}"], "2026");
+        assert_eq!(keywords["{{$X:nope}}"], "");
+        assert_eq!(keywords["{{$Y:shout}}"], "Y");
+    }
+
     #[test]
     fn find_and_exec_resolves_json_path() {
         let re = keyword_re();
@@ -341,7 +898,13 @@ mod tests {
         let json_data = serde_json::json!({ "user": { "name": "spark" } });
 
         // jq filters need a leading '.', so the placeholder is {{$.user.name}}
-        Fns::find_and_exec("Hello {{$.user.name}}", &mut keywords, &re, &json_data);
+        Fns::find_and_exec(
+            "Hello {{$.user.name}}",
+            &mut keywords,
+            &re,
+            &json_data,
+            &Context::new(),
+        );
 
         assert_eq!(
             keywords
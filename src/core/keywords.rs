@@ -1,6 +1,70 @@
 use crate::Keywords;
-use chrono::{Datelike, Local, Utc};
-use std::{collections::HashMap, env};
+use chrono::{DateTime, Datelike, FixedOffset, Local, Utc};
+use std::{collections::HashMap, env, path::PathBuf};
+
+/// Snapshot of the process environment that built-in keywords such as
+/// `{{$HOME}}` and `{{$CURRENTDIR}}` are derived from.
+///
+/// [`Environment::capture`] reads the real process state; building one by hand
+/// makes renders independent of the machine they run on.
+///
+/// # Examples
+/// ```rust
+/// use spark::Environment;
+///
+/// let env = Environment::new()
+///     .with_var("HOME", "/home/ci")
+///     .with_current_dir("/work/demo");
+///
+/// assert_eq!(env.var("HOME"), Some("/home/ci"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    vars: HashMap<String, String>,
+    current_dir: Option<PathBuf>,
+}
+
+impl Environment {
+    /// Creates an empty environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the environment variables and working directory of the current process.
+    pub fn capture() -> Self {
+        Self {
+            vars: env::vars().collect(),
+            current_dir: env::current_dir().ok(),
+        }
+    }
+
+    /// Sets an environment variable.
+    pub fn with_var(mut self, key: impl Into<String>, val: impl Into<String>) -> Self {
+        self.vars.insert(key.into(), val.into());
+        self
+    }
+
+    /// Sets the working directory `{{$CURRENTDIR}}` is derived from.
+    pub fn with_current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Looks up an environment variable.
+    pub fn var(&self, key: &str) -> Option<&str> {
+        self.vars.get(key).map(String::as_str)
+    }
+
+    /// Returns all environment variables.
+    pub fn vars(&self) -> &HashMap<String, String> {
+        &self.vars
+    }
+
+    /// Returns the working directory, if known.
+    pub fn current_dir(&self) -> Option<&std::path::Path> {
+        self.current_dir.as_deref()
+    }
+}
 
 impl Keywords {
     pub fn from(name: &str, function: Option<&str>) -> String {
@@ -18,37 +82,53 @@ impl Keywords {
     }
 
     pub fn init() -> HashMap<String, String> {
+        Self::init_with(Local::now().fixed_offset(), &Environment::capture())
+    }
+
+    /// Builds the built-in keywords from an explicit clock reading and environment
+    /// snapshot instead of the live process state.
+    pub fn init_with(
+        now: DateTime<FixedOffset>,
+        environment: &Environment,
+    ) -> HashMap<String, String> {
         let mut keywords = HashMap::new();
 
-        if let Ok(home) = env::var("HOME") {
-            keywords.insert(Self::from("HOME", None), home);
+        if let Some(home) = environment.var("HOME") {
+            keywords.insert(Self::from("HOME", None), home.to_string());
         }
 
         keywords.insert(Self::from("PROJECTNAME", None), String::new());
 
-        if let Ok(current_dir) = env::current_dir() {
+        if let Some(current_dir) = environment.current_dir() {
             if let Some(dir_name) = current_dir.file_name().and_then(|n| n.to_str()) {
                 keywords.insert(Self::from("CURRENTDIR", None), dir_name.to_string());
             }
         }
 
-        keywords.insert(Self::from("NOW_UTC", None), Utc::now().to_string());
-        keywords.insert(Self::from("NOW", None), Local::now().to_string());
-        keywords.insert(Self::from("YYYY", None), Local::now().year().to_string());
-        keywords.insert(
-            Self::from("YY", None),
-            Local::now().format("%y").to_string(),
-        );
-        keywords.insert(Self::from("MM", None), Local::now().month().to_string());
-        keywords.insert(Self::from("DD", None), Local::now().day().to_string());
-
-        for (key, value) in env::vars() {
-            keywords.insert(Self::from(&key, None), value);
+        keywords.extend(Self::dates(now));
+
+        for (key, value) in environment.vars() {
+            keywords.insert(Self::from(key, None), value.clone());
         }
 
         keywords
     }
 
+    /// Date keywords (`NOW`, `NOW_UTC`, `YYYY`, `YY`, `MM`, `DD`) for the given instant.
+    pub fn dates(now: DateTime<FixedOffset>) -> HashMap<String, String> {
+        HashMap::from([
+            (
+                Self::from("NOW_UTC", None),
+                now.with_timezone(&Utc).to_string(),
+            ),
+            (Self::from("NOW", None), now.to_string()),
+            (Self::from("YYYY", None), now.year().to_string()),
+            (Self::from("YY", None), now.format("%y").to_string()),
+            (Self::from("MM", None), now.month().to_string()),
+            (Self::from("DD", None), now.day().to_string()),
+        ])
+    }
+
     pub fn replace_keywords(keywords: &HashMap<String, String>, data: &str) -> String {
         let mut output = data.to_string();
         for (key, value) in keywords.iter() {
@@ -60,7 +140,7 @@ impl Keywords {
 
 #[cfg(test)]
 mod tests {
-    use super::Keywords;
+    use super::{Environment, Keywords};
     use std::collections::HashMap;
 
     #[test]
@@ -92,6 +172,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn init_with_is_deterministic() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z").unwrap();
+        let env = Environment::new()
+            .with_var("HOME", "/home/ci")
+            .with_var("CI", "true")
+            .with_current_dir("/work/demo");
+
+        let keywords = Keywords::init_with(now, &env);
+        assert_eq!(keywords, Keywords::init_with(now, &env));
+
+        assert_eq!(keywords["{{$NOW}}"], "2026-01-02 03:04:05 +00:00");
+        assert_eq!(keywords["{{$NOW_UTC}}"], "2026-01-02 03:04:05 UTC");
+        assert_eq!(keywords["{{$YYYY}}"], "2026");
+        assert_eq!(keywords["{{$YY}}"], "26");
+        assert_eq!(keywords["{{$MM}}"], "1");
+        assert_eq!(keywords["{{$DD}}"], "2");
+        assert_eq!(keywords["{{$HOME}}"], "/home/ci");
+        assert_eq!(keywords["{{$CURRENTDIR}}"], "demo");
+        assert_eq!(keywords["{{$CI}}"], "true");
+        assert!(!keywords.contains_key("{{$PATH}}"));
+    }
+
     #[test]
     fn replace_keywords_substitutes_all_matches() {
         let mut map = HashMap::new();
@@ -73,24 +73,42 @@
 //! ```
 
 pub mod context;
+pub mod data;
 pub mod error;
+pub mod events;
 pub mod funcs;
 pub mod keywords;
 pub mod output_target;
 pub mod templates;
+mod transaction;
 mod utils;
+pub mod vars;
 
 pub use context::Context;
 pub use error::{Error, Result};
+pub use funcs::FunctionRegistry;
+use indexmap::IndexMap;
+pub use keywords::Environment;
 pub use output_target::OutputTarget;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-pub use templates::{Options, RenderedFile};
+use std::path::PathBuf;
+pub use templates::capture::{Capture, Suggestion};
+pub use templates::lint::{Diagnostic, Severity};
+pub use templates::manifest::Manifest;
+pub use templates::plan::{Input, Plan};
+pub use templates::snapshot::{SnapshotCase, SnapshotDiff, SnapshotOutcome};
+pub use templates::strict::UnknownKey;
+pub use templates::update::{FileUpdate, UpdateReport, UpdateStatus};
+pub use templates::{Options, RenderedFile, Variable, VariableSpec};
 
 /// Utility container for keyword operations and default variable initializations.
 pub struct Keywords {}
 
 /// Template metadata information.
-#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+#[non_exhaustive]
 pub struct Information {
     /// Template name.
     pub name: Option<String>,
@@ -98,25 +116,52 @@ pub struct Information {
     pub author: Option<String>,
     /// Brief template description.
     pub description: Option<String>,
+    /// Free-form metadata (`[info.extra]`), e.g. links or tags. Spark ignores it.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub extra: IndexMap<String, serde_json::Value>,
 }
 
 impl Information {
     /// Creates a new `Information` metadata container.
-    pub fn new(
-        name: Option<String>,
-        author: Option<String>,
-        description: Option<String>,
-    ) -> Self {
+    pub fn new(name: Option<String>, author: Option<String>, description: Option<String>) -> Self {
         Self {
             name,
             author,
             description,
+            extra: IndexMap::new(),
         }
     }
+
+    /// Adds a free-form `[info.extra]` entry.
+    pub fn with_extra(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.extra.insert(key.into(), value);
+        self
+    }
+
+    /// Labelled fields for display, with `-` for the ones not set.
+    ///
+    /// ```rust
+    /// use spark::Information;
+    ///
+    /// let info = Information::new(Some("service".into()), None, None);
+    /// assert_eq!(
+    ///     info.summary(),
+    ///     [("Name", "service"), ("Description", "-"), ("Author", "-")]
+    /// );
+    /// ```
+    pub fn summary(&self) -> [(&'static str, &str); 3] {
+        [
+            ("Name", &self.name),
+            ("Description", &self.description),
+            ("Author", &self.author),
+        ]
+        .map(|(label, value)| (label, value.as_deref().unwrap_or("-")))
+    }
 }
 
 /// A file definition entry within a template.
-#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct File {
     /// Target path template (supports placeholders and target URIs).
     pub path: String,
@@ -125,21 +170,45 @@ pub struct File {
 }
 
 /// Represents a complete Spark template with metadata, configuration options, and files.
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+#[non_exhaustive]
 pub struct Template {
     /// Optional metadata about the template.
     pub info: Option<Information>,
     /// Execution and engine options.
     pub options: Option<Options>,
+    /// Variables keyed by name (`NAME` for `{{$NAME}}`): a default value used when
+    /// the context does not already provide the variable, or a table with prompt
+    /// settings.
+    pub variables: Option<IndexMap<String, Variable>>,
     /// List of file templates to render.
     pub files: Option<Vec<File>>,
+    /// Path the template was loaded from, used to resolve Liquid partials.
+    #[serde(skip)]
+    pub(crate) source: Option<PathBuf>,
+    /// TOML text the template was parsed from, used to report source positions.
+    #[serde(skip)]
+    pub(crate) raw: Option<String>,
 }
 
 /// Dynamic function modifiers for template keywords (e.g. `:read`).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Fns {
     /// Prompts the user interactively on `stdin` for input.
     Read,
+    /// Calls a function registered in the context's [`FunctionRegistry`].
+    Call(String),
+    /// Generates a random (v4) UUID.
+    Uuid,
+    /// Generates a random alphanumeric string of the given length (`random(32)`).
+    Random(usize),
+    /// Generates a random integer in the inclusive range (`randint(8000,9000)`).
+    RandInt(i64, i64),
+    /// Formats the current date and time with a `strftime` pattern (`date(%Y-%m-%d)`).
+    Date(String),
+    /// Hex encoded SHA-256 digest of the variable's own value (`{{$X:sha256}}`).
+    Sha256,
     /// Plain variable without function evaluation.
     None,
 }
@@ -205,24 +274,33 @@ content = "Hello"
 
     #[test]
     fn template_serializes_roundtrip() {
-        let template = Template {
-            info: Some(Information {
-                name: Some("roundtrip".into()),
-                author: Some("tester".into()),
-                description: Some("desc".into()),
-            }),
-            options: Some(Options {
+        let template = Template::builder()
+            .with_info(
+                Information::new(
+                    Some("roundtrip".into()),
+                    Some("tester".into()),
+                    Some("desc".into()),
+                )
+                .with_extra("homepage", serde_json::json!("https://example.com")),
+            )
+            .with_options(Options {
                 git: false,
                 use_liquid: Some(true),
                 json_data: None,
                 project_root: "proj".into(),
-            }),
-            files: Some(vec![File::create("a.txt", "b")]),
-        };
+            })
+            .with_variable("LICENSE", "MIT")
+            .with_file(File::create("a.txt", "b"));
 
         let encoded = toml::to_string(&template).unwrap();
         let decoded: Template = toml::from_str(&encoded).unwrap();
-        assert_eq!(decoded.info.unwrap().name.as_deref(), Some("roundtrip"));
+        let info = decoded.info.unwrap();
+        assert_eq!(info.name.as_deref(), Some("roundtrip"));
+        assert_eq!(info.extra["homepage"], "https://example.com");
+        assert_eq!(
+            decoded.variables.unwrap()["LICENSE"].default_value(),
+            Some("MIT")
+        );
         assert_eq!(decoded.files.unwrap()[0].content, "b");
     }
 
@@ -252,10 +330,8 @@ content = "Hello"
 
     #[test]
     fn template_render_non_interactive_fails_on_missing_read_variable() {
-        let template = Template::builder().with_file(File::create(
-            "greeting.txt",
-            "Welcome, {{$NAME:read}}!",
-        ));
+        let template =
+            Template::builder().with_file(File::create("greeting.txt", "Welcome, {{$NAME:read}}!"));
 
         let context = Context::new().non_interactive();
         let result = template.render(&context);
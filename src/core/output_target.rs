@@ -23,10 +23,9 @@
 //! [`OutputTarget::from_path`] and [`OutputTarget::write`].  No changes to
 //! the rendering pipeline are required.
 
-use colored::Colorize;
 use std::{
     io::{self, Write},
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
 };
 
 /// The sink to which rendered template output is directed.
@@ -82,10 +81,76 @@ impl OutputTarget {
         Self::File(PathBuf::from(path))
     }
 
+    /// Filesystem path of a [`OutputTarget::File`] after shell expansion (`~`,
+    /// `$VAR`), exactly as [`OutputTarget::write`] will use it. `None` for streams.
+    pub fn expanded_path(&self) -> Option<PathBuf> {
+        let Self::File(path) = self else {
+            return None;
+        };
+        let path_str = path.to_string_lossy();
+        Some(match shellexpand::full(&path_str) {
+            Ok(e) => PathBuf::from(e.as_ref()),
+            Err(_) => path.clone(),
+        })
+    }
+
+    /// Places a relative file target under `root`. Absolute and `~` paths and
+    /// stream targets are returned unchanged.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::OutputTarget;
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// let target = OutputTarget::from_path("src/main.rs").under(Path::new("/srv/app"));
+    /// assert_eq!(target, OutputTarget::File(PathBuf::from("/srv/app/src/main.rs")));
+    /// assert_eq!(OutputTarget::from_path("stdout://").under(Path::new("/srv/app")), OutputTarget::Stdout);
+    /// ```
+    pub fn under(self, root: &Path) -> Self {
+        match self.expanded_path() {
+            Some(path) if path.is_relative() => Self::File(root.join(path)),
+            _ => self,
+        }
+    }
+
+    /// Checks that a file target stays inside `root` once shell expansion, `..`
+    /// components and existing symlinks are resolved. Stream targets always pass.
+    ///
+    /// # Errors
+    /// Returns [`crate::Error::PathEscape`] if the file would land outside `root`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::OutputTarget;
+    ///
+    /// let root = std::env::temp_dir();
+    /// assert!(OutputTarget::from_path("project/src/main.rs").ensure_within(&root).is_ok());
+    /// assert!(OutputTarget::from_path("../../etc/passwd").ensure_within(&root).is_err());
+    /// assert!(OutputTarget::from_path("stdout://").ensure_within(&root).is_ok());
+    /// ```
+    pub fn ensure_within(&self, root: &Path) -> crate::Result<()> {
+        let Some(path) = self.expanded_path() else {
+            return Ok(());
+        };
+
+        let root = resolve(root);
+        let target = resolve(&root.join(&path));
+        if target.starts_with(&root) && target != root {
+            Ok(())
+        } else {
+            Err(crate::Error::PathEscape {
+                path: path.to_string_lossy().to_string(),
+                root,
+            })
+        }
+    }
+
     /// Write `content` to the target.
     ///
     /// For [`OutputTarget::File`] the path is shell-expanded (same behaviour
-    /// as the existing [`crate::utils::write_content`]).
+    /// as the existing [`crate::utils::write_content`]) and missing parent
+    /// directories are created. Nothing is printed; extraction reports writes as
+    /// [`crate::events::Event::FileWritten`].
     pub fn write(&self, content: &str) -> std::io::Result<()> {
         match self {
             Self::Stdout => {
@@ -101,28 +166,20 @@ impl OutputTarget {
                 handle.flush()
             }
             Self::Clipboard => {
-                let mut clipboard = arboard::Clipboard::new()
-                    .map_err(|e| io::Error::other(e))?;
-                clipboard
-                    .set_text(content)
-                    .map_err(|e| io::Error::other(e))?;
-                println!("{}", "copied to clipboard".blue());
-                Ok(())
+                let mut clipboard = arboard::Clipboard::new().map_err(io::Error::other)?;
+                clipboard.set_text(content).map_err(io::Error::other)
             }
-            Self::File(path) => {
-                let path_str = path.to_string_lossy();
-                let expanded = match shellexpand::full(&path_str) {
-                    Ok(e) => e.to_string(),
-                    Err(_) => path_str.to_string(),
-                };
+            Self::File(_) => {
+                let expanded = self
+                    .expanded_path()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
                 // Create parent directories if needed (mirrors the existing
                 // behaviour that was previously in `prepare_file_content`).
                 if let Some(parent) = std::path::Path::new(&expanded).parent() {
-                    let parent_str = parent.to_string_lossy();
-                    if !parent_str.is_empty() {
-                        if let Err(e) = std::fs::create_dir_all(parent) {
-                            eprintln!("{}: {}", "error".red(), e);
-                        }
+                    if !parent.as_os_str().is_empty() {
+                        std::fs::create_dir_all(parent)?;
                     }
                 }
                 // Preserve the existing behaviour: replace the legacy
@@ -131,14 +188,52 @@ impl OutputTarget {
                     std::path::Path::new(&expanded),
                     content.replace("initPJNAME", "{{$PROJECTNAME}}"),
                 )
-                .map(|_| {
-                    println!("{}: {}", "file written".blue(), expanded.bold().green());
-                })
             }
         }
     }
 }
 
+/// Symlinks followed in a row before giving up, like the OS's `ELOOP` limit.
+const MAX_LINKS: usize = 40;
+
+/// Makes `path` absolute and resolves it one component at a time the way the OS
+/// would: existing symlinks are followed before a later `..` is applied, so
+/// `link/..` is the parent of the link's target, not the directory of the link.
+fn resolve(path: &Path) -> PathBuf {
+    resolve_links(path, 0)
+}
+
+fn resolve_links(path: &Path, depth: usize) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => {
+                resolved.push(other);
+                let is_link = std::fs::symlink_metadata(&resolved)
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                if !is_link || depth >= MAX_LINKS {
+                    continue;
+                }
+                if let Ok(target) = std::fs::read_link(&resolved) {
+                    resolved.pop();
+                    resolved = resolve_links(&resolved.join(target), depth + 1);
+                }
+            }
+        }
+    }
+    resolved
+}
+
 #[cfg(test)]
 mod tests {
     use super::OutputTarget;
@@ -380,4 +475,88 @@ mod tests {
             assert_eq!(e.kind(), std::io::ErrorKind::Other);
         }
     }
+
+    // ── ensure_within sandbox ────────────────────────────────────────────────
+
+    fn sandbox(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn relative_paths_inside_root_are_allowed() {
+        let root = sandbox("spark_test_sandbox_inside");
+        for path in [
+            "a.txt",
+            "nested/dir/b.txt",
+            "./c.txt",
+            "x/../d.txt",
+            "file://e.txt",
+        ] {
+            assert!(
+                OutputTarget::from_path(path).ensure_within(&root).is_ok(),
+                "{}",
+                path
+            );
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn escaping_paths_are_rejected() {
+        let root = sandbox("spark_test_sandbox_escape");
+        let inside_absolute = root.join("ok.txt").to_string_lossy().to_string();
+        assert!(OutputTarget::from_path(&inside_absolute)
+            .ensure_within(&root)
+            .is_ok());
+
+        for path in [
+            "../x.txt",
+            "a/../../x.txt",
+            "/etc/passwd",
+            "~/.ssh/authorized_keys",
+            ".",
+        ] {
+            let err = OutputTarget::from_path(path)
+                .ensure_within(&root)
+                .expect_err(path);
+            assert!(matches!(err, crate::Error::PathEscape { .. }), "{}", path);
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_leaving_root_are_rejected() {
+        let root = sandbox("spark_test_sandbox_symlink");
+        std::os::unix::fs::symlink(std::env::temp_dir(), root.join("link")).unwrap();
+
+        let err = OutputTarget::from_path("link/x.txt").ensure_within(&root);
+        assert!(matches!(err, Err(crate::Error::PathEscape { .. })));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parent_of_a_symlink_is_resolved_after_following_it() {
+        let root = sandbox("spark_test_sandbox_symlink_parent");
+        let outside = sandbox("spark_test_sandbox_symlink_parent_outside");
+        std::fs::create_dir_all(outside.join("deep")).unwrap();
+        std::fs::create_dir_all(root.join("real/sub")).unwrap();
+        std::os::unix::fs::symlink(outside.join("deep"), root.join("away")).unwrap();
+        std::os::unix::fs::symlink(root.join("real/sub"), root.join("near")).unwrap();
+
+        // Lexically inside the root, but `away/..` is `outside`.
+        let err = OutputTarget::from_path("away/../x.txt").ensure_within(&root);
+        assert!(matches!(err, Err(crate::Error::PathEscape { .. })));
+        assert!(OutputTarget::from_path("near/../x.txt")
+            .ensure_within(&root)
+            .is_ok());
+
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_dir_all(&outside);
+    }
 }
@@ -0,0 +1,449 @@
+//! Turning an existing directory into a template (`spark init`).
+//!
+//! [`Capture::scan`] collects the text files of a directory, skipping what
+//! `.gitignore` and `.sparkignore` exclude as well as VCS metadata and common
+//! build output ([`DEFAULT_EXCLUDES`]). [`Capture::suggest`] then looks for
+//! strings worth turning into variables, such as the directory name, and
+//! [`Capture::into_template`] applies the accepted ones:
+//!
+//! ```rust
+//! use spark::{Capture, Information};
+//!
+//! # let dir = std::env::temp_dir().join("spark-capture-doc");
+//! # std::fs::create_dir_all(&dir).unwrap();
+//! # std::fs::write(dir.join("README.md"), "# spark-capture-doc").unwrap();
+//! let capture = Capture::scan(&dir)?;
+//! let suggestions = capture.suggest(&[]);
+//! let template = capture.into_template(&suggestions, Information::default());
+//!
+//! assert_eq!(template.files.unwrap()[0].content, "# {{$PROJECTNAME}}");
+//! # Ok::<(), spark::Error>(())
+//! ```
+
+use crate::{File, Information, Keywords, Options, Template, Variable};
+use heck::{ToKebabCase, ToSnakeCase, ToUpperCamelCase};
+use ignore::WalkBuilder;
+use indexmap::IndexMap;
+use regex::{NoExpand, Regex};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Per-directory ignore file read in addition to `.gitignore`, same syntax.
+pub const SPARKIGNORE: &str = ".sparkignore";
+
+/// Directories never captured: VCS metadata, Spark's own files and build output.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    ".spark",
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "out",
+    "__pycache__",
+    ".venv",
+    "venv",
+    ".tox",
+    ".next",
+    ".gradle",
+];
+
+/// Files of a directory ready to become a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    /// Name of the captured directory.
+    pub name: String,
+    /// Captured text files, with paths relative to the directory.
+    pub files: Vec<File>,
+    /// Files left out because they are not UTF-8 text.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// A string found in the captured files that could be replaced by a variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// Variable the value would become, e.g. `PROJECTNAME`.
+    pub variable: String,
+    /// Literal text found in the files.
+    pub value: String,
+    /// What the value is replaced with, e.g. `{{$PROJECTNAME}}`.
+    pub replacement: String,
+    /// Whether the replacement also applies to file paths. Liquid expressions
+    /// only work in file contents.
+    pub in_paths: bool,
+    /// Default recorded in `[variables]`, so rendering reproduces the original.
+    pub default: Option<String>,
+    /// Number of occurrences in file paths and contents.
+    pub occurrences: usize,
+}
+
+impl Capture {
+    /// Collects the text files under `root`.
+    pub fn scan(root: &Path) -> crate::Result<Self> {
+        if !root.is_dir() {
+            return Err(crate::Error::InvalidPath(root.to_path_buf()));
+        }
+
+        let name = fs::canonicalize(root)?
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let walker = WalkBuilder::new(root)
+            .hidden(false)
+            .require_git(false)
+            .add_custom_ignore_filename(SPARKIGNORE)
+            .filter_entry(|entry| {
+                !(entry.file_type().is_some_and(|t| t.is_dir())
+                    && DEFAULT_EXCLUDES.contains(&entry.file_name().to_string_lossy().as_ref()))
+            })
+            .sort_by_file_path(Path::cmp)
+            .build();
+
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        for entry in walker {
+            let entry = entry.map_err(|e| crate::Error::Custom(e.to_string()))?;
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            match fs::read_to_string(entry.path()) {
+                Ok(content) => files.push(File::new(
+                    relative.to_string_lossy().replace('\\', "/"),
+                    content,
+                )),
+                Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                    skipped.push(relative.to_path_buf())
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(Self {
+            name,
+            files,
+            skipped,
+        })
+    }
+
+    /// Whether any file already contains Liquid-like syntax (`{{`, `{%`) that
+    /// Spark would try to render. Such captures disable Liquid.
+    pub fn has_liquid_syntax(&self) -> bool {
+        self.files
+            .iter()
+            .any(|file| file.content.contains("{{") || file.content.contains("{%"))
+    }
+
+    /// Strings worth replacing, most occurrences first: the directory name as
+    /// `{{$PROJECTNAME}}`, its snake, kebab and Pascal case spellings (as Liquid
+    /// filters, unless Liquid is disabled) and each `(variable, value)` candidate,
+    /// e.g. the git author. Values that do not occur are left out.
+    pub fn suggest(&self, candidates: &[(String, String)]) -> Vec<Suggestion> {
+        let mut suggestions = Vec::new();
+        let mut seen = Vec::new();
+
+        let project = "PROJECTNAME";
+        let placeholder = Keywords::from(project, None);
+        if !self.name.is_empty() {
+            suggestions.extend(self.suggestion(
+                project,
+                &self.name,
+                placeholder.clone(),
+                true,
+                None,
+            ));
+            seen.push(self.name.clone());
+
+            if !self.has_liquid_syntax() {
+                let variants = [
+                    (self.name.to_snake_case(), "snake_case"),
+                    (self.name.to_kebab_case(), "kebab_case"),
+                    (self.name.to_upper_camel_case(), "pascal_case"),
+                ];
+                for (value, filter) in variants {
+                    if seen.contains(&value) {
+                        continue;
+                    }
+                    let replacement = format!("{{{{ \"{}\" | {} }}}}", placeholder, filter);
+                    suggestions.extend(self.suggestion(project, &value, replacement, false, None));
+                    seen.push(value);
+                }
+            }
+        }
+
+        for (variable, value) in candidates {
+            if value.trim().is_empty() || seen.contains(value) {
+                continue;
+            }
+            let variable = variable_name(variable);
+            let placeholder = Keywords::from(&variable, None);
+            suggestions.extend(self.suggestion(
+                &variable,
+                value,
+                placeholder,
+                true,
+                Some(value.clone()),
+            ));
+            seen.push(value.clone());
+        }
+
+        suggestions.sort_by_key(|s| std::cmp::Reverse(s.occurrences));
+        suggestions
+    }
+
+    fn suggestion(
+        &self,
+        variable: &str,
+        value: &str,
+        replacement: String,
+        in_paths: bool,
+        default: Option<String>,
+    ) -> Option<Suggestion> {
+        let pattern = whole_word(value);
+        let occurrences = self
+            .files
+            .iter()
+            .map(|file| {
+                let in_path = if in_paths {
+                    pattern.find_iter(&file.path).count()
+                } else {
+                    0
+                };
+                in_path + pattern.find_iter(&file.content).count()
+            })
+            .sum();
+
+        (occurrences > 0).then(|| Suggestion {
+            variable: variable.to_string(),
+            value: value.to_string(),
+            replacement,
+            in_paths,
+            default,
+            occurrences,
+        })
+    }
+
+    /// Builds the template, replacing every accepted suggestion where it stands
+    /// as a whole word. Longer values are replaced first so a value containing
+    /// another one is not split.
+    /// Liquid is disabled if the files already contain Liquid-like syntax.
+    pub fn into_template(self, accepted: &[Suggestion], info: Information) -> Template {
+        let options = self.has_liquid_syntax().then(|| Options {
+            use_liquid: Some(false),
+            json_data: None,
+            ..Options::default()
+        });
+        let mut accepted: Vec<&Suggestion> = accepted.iter().collect();
+        accepted.sort_by_key(|s| std::cmp::Reverse(s.value.len()));
+        let patterns: Vec<Regex> = accepted.iter().map(|s| whole_word(&s.value)).collect();
+
+        let files = self
+            .files
+            .into_iter()
+            .map(|file| {
+                let (path, content) = accepted.iter().zip(&patterns).fold(
+                    (file.path, file.content),
+                    |(path, content), (suggestion, pattern)| {
+                        let replace = |text: &str| {
+                            let replacement = NoExpand(&suggestion.replacement);
+                            pattern.replace_all(text, replacement).into_owned()
+                        };
+                        let path = match suggestion.in_paths {
+                            true => replace(&path),
+                            false => path,
+                        };
+                        let content = replace(&content);
+                        (path, content)
+                    },
+                );
+                File::new(path, content)
+            })
+            .collect();
+
+        let variables: IndexMap<String, Variable> = accepted
+            .iter()
+            .filter_map(|s| Some((s.variable.clone(), s.default.clone()?.into())))
+            .collect();
+
+        Template {
+            info: Some(info),
+            options,
+            variables: (!variables.is_empty()).then_some(variables),
+            files: Some(files),
+            ..Default::default()
+        }
+    }
+}
+
+/// Matches `value` only where it is not part of a longer word, so a project
+/// called `app` leaves `happy` and `application` alone. Ends of the value that
+/// are not word characters (as in `@example.com`) need no boundary.
+fn whole_word(value: &str) -> Regex {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let start = if is_word(value.chars().next()) {
+        r"\b"
+    } else {
+        ""
+    };
+    let end = if is_word(value.chars().last()) {
+        r"\b"
+    } else {
+        ""
+    };
+    Regex::new(&format!("{}{}{}", start, regex::escape(value), end))
+        .expect("escaped value is a valid pattern")
+}
+
+/// Variable name as placeholders spell it: trimmed and upper case.
+fn variable_name(name: &str) -> String {
+    name.trim().to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("spark-capture-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn scan_respects_ignore_files_and_default_excludes() {
+        let dir = project("scan");
+        for (path, content) in [
+            (".gitignore", "*.log\n"),
+            (".sparkignore", "secrets/\n"),
+            (".github/workflows/ci.yml", "on: push\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("debug.log", "noise\n"),
+            ("secrets/key.pem", "x\n"),
+            ("target/debug/app", "bin\n"),
+            ("node_modules/x/index.js", "x\n"),
+            (".git/HEAD", "ref\n"),
+        ] {
+            fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+            fs::write(dir.join(path), content).unwrap();
+        }
+        fs::write(dir.join("logo.png"), [0x89, 0x50, 0xff, 0xfe]).unwrap();
+
+        let capture = Capture::scan(&dir).unwrap();
+        let paths: Vec<_> = capture.files.iter().map(|f| f.path.as_str()).collect();
+
+        assert_eq!(
+            paths,
+            [
+                ".github/workflows/ci.yml",
+                ".gitignore",
+                ".sparkignore",
+                "src/main.rs"
+            ]
+        );
+        assert_eq!(capture.skipped, [PathBuf::from("logo.png")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn suggestions_cover_project_name_spellings_and_candidates() {
+        let capture = Capture {
+            name: "my-app".to_string(),
+            files: vec![
+                File::create("my-app/README.md", "# my-app by Jane"),
+                File::create("src/lib.rs", "mod my_app; struct MyApp;"),
+            ],
+            skipped: Vec::new(),
+        };
+
+        let suggestions = capture.suggest(&[
+            ("author".to_string(), "Jane".to_string()),
+            ("EMAIL".to_string(), "jane@example.com".to_string()),
+        ]);
+        let found: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.variable.as_str(), s.value.as_str(), s.occurrences))
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                ("PROJECTNAME", "my-app", 2),
+                ("PROJECTNAME", "my_app", 1),
+                ("PROJECTNAME", "MyApp", 1),
+                ("AUTHOR", "Jane", 1),
+            ]
+        );
+
+        let template = capture.into_template(&suggestions, Information::default());
+        let files = template.files.unwrap();
+        assert_eq!(files[0].path, "{{$PROJECTNAME}}/README.md");
+        assert_eq!(files[0].content, "# {{$PROJECTNAME}} by {{$AUTHOR}}");
+        assert_eq!(
+            files[1].content,
+            r#"mod {{ "{{$PROJECTNAME}}" | snake_case }}; struct {{ "{{$PROJECTNAME}}" | pascal_case }};"#
+        );
+        assert_eq!(
+            template.variables.unwrap()["AUTHOR"].default_value(),
+            Some("Jane")
+        );
+        assert!(template.options.is_none());
+    }
+
+    #[test]
+    fn short_values_only_match_whole_words() {
+        let capture = Capture {
+            name: "app".to_string(),
+            files: vec![
+                File::create("app/happy.txt", "app: happy application, apple (app)"),
+                File::create("apples/README.md", "By Al, not Alice"),
+            ],
+            skipped: Vec::new(),
+        };
+
+        let suggestions = capture.suggest(&[("AUTHOR".to_string(), "Al".to_string())]);
+        let found: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.value.as_str(), s.occurrences))
+            .collect();
+        assert_eq!(found, [("app", 3), ("Al", 1)]);
+
+        let files = capture
+            .into_template(&suggestions, Information::default())
+            .files
+            .unwrap();
+        assert_eq!(files[0].path, "{{$PROJECTNAME}}/happy.txt");
+        assert_eq!(
+            files[0].content,
+            "{{$PROJECTNAME}}: happy application, apple ({{$PROJECTNAME}})"
+        );
+        assert_eq!(files[1].path, "apples/README.md");
+        assert_eq!(files[1].content, "By {{$AUTHOR}}, not Alice");
+    }
+
+    #[test]
+    fn liquid_is_disabled_when_files_contain_liquid_syntax() {
+        let capture = Capture {
+            name: "my-app".to_string(),
+            files: vec![File::create(
+                "ci.yml",
+                "name: my_app\ntoken: ${{ secrets.TOKEN }}",
+            )],
+            skipped: Vec::new(),
+        };
+
+        let suggestions = capture.suggest(&[]);
+        assert!(suggestions.is_empty());
+
+        let template = capture.into_template(&suggestions, Information::default());
+        assert_eq!(template.options.unwrap().use_liquid, Some(false));
+    }
+}
@@ -0,0 +1,442 @@
+//! Spark-specific Liquid filters and partial loading.
+//!
+//! The Liquid stdlib covers generic text manipulation; the filters here fill
+//! the gaps that come up when scaffolding code:
+//!
+//! | Filter         | Example                                  | Output                |
+//! |----------------|------------------------------------------|-----------------------|
+//! | `snake_case`   | `{{ "MyProject" \| snake_case }}`        | `my_project`          |
+//! | `kebab_case`   | `{{ "MyProject" \| kebab_case }}`        | `my-project`          |
+//! | `pascal_case`  | `{{ "my_project" \| pascal_case }}`      | `MyProject`           |
+//! | `pluralize`    | `{{ "category" \| pluralize }}`          | `categories`          |
+//! | `indent`       | `{{ body \| indent: 4 }}`                | every line indented   |
+//! | `json`         | `{{ value \| json }}`                    | JSON encoded value    |
+//! | `toml`         | `{{ value \| toml }}`                    | TOML encoded value    |
+//! | `sha256`       | `{{ "text" \| sha256 }}`                 | hex encoded digest    |
+//! | `uuid`         | `{{ "name" \| uuid }}`                   | name-based UUID (v5)  |
+//!
+//! `{% include "name" %}` resolves `name` (or `name.liquid`) relative to the
+//! directory the template was loaded from.
+
+use crate::funcs::sha256_hex;
+use heck::{ToKebabCase, ToSnakeCase, ToUpperCamelCase};
+use liquid::partials::{LazyCompiler, PartialSource};
+use liquid_core::{
+    Display_filter, Expression, Filter, FilterParameters, FilterReflection, FromFilterParameters,
+    ParseFilter, Result, Runtime, Value, ValueView,
+};
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Extension used for partial templates included with `{% include %}`.
+pub const PARTIAL_EXTENSION: &str = "liquid";
+
+/// Builds the Liquid parser used by Spark: the stdlib plus Spark filters, with
+/// partials read from `partials_dir` when given.
+pub fn parser(partials_dir: Option<&Path>) -> std::result::Result<liquid::Parser, liquid::Error> {
+    let builder = liquid::ParserBuilder::with_stdlib()
+        .filter(SnakeCase)
+        .filter(KebabCase)
+        .filter(PascalCase)
+        .filter(Pluralize)
+        .filter(Indent)
+        .filter(Json)
+        .filter(Toml)
+        .filter(Sha256Hex)
+        .filter(Uuid);
+
+    match partials_dir {
+        Some(dir) => builder
+            .partials(LazyCompiler::new(PartialDir(dir.to_path_buf())))
+            .build(),
+        None => builder.build(),
+    }
+}
+
+/// Partials in a directory, read from disk only when they are included:
+/// `name` and `name.liquid` both refer to `<dir>/name.liquid`.
+#[derive(Debug)]
+struct PartialDir(PathBuf);
+
+impl PartialDir {
+    /// File for `name`, which must stay inside the directory.
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        let relative = Path::new(name);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+
+        let path = self.0.join(relative);
+        Some(match path.extension() {
+            Some(ext) if ext == PARTIAL_EXTENSION => path,
+            _ => self.0.join(format!("{}.{}", name, PARTIAL_EXTENSION)),
+        })
+    }
+}
+
+impl PartialSource for PartialDir {
+    fn contains(&self, name: &str) -> bool {
+        self.path(name).is_some_and(|path| path.is_file())
+    }
+
+    /// Partials are not listed up front; that would mean reading the whole
+    /// directory tree for every parser.
+    fn names(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    fn try_get<'a>(&'a self, name: &str) -> Option<Cow<'a, str>> {
+        let content = fs::read_to_string(self.path(name)?).ok()?;
+        Some(Cow::Owned(content))
+    }
+}
+
+/// Naive English pluralization covering the common scaffolding cases.
+pub fn pluralize(word: &str) -> String {
+    let lower = word.to_lowercase();
+
+    if lower.is_empty() {
+        return String::new();
+    }
+
+    // `CITY` becomes `CITIES`, not `CITYs`.
+    let shouting = word.chars().any(char::is_alphabetic) && !word.chars().any(char::is_lowercase);
+    let suffix = |suffix: &str| match shouting {
+        true => suffix.to_uppercase(),
+        false => suffix.to_string(),
+    };
+
+    if let Some(stem) = word.strip_suffix(['y', 'Y']) {
+        let before = stem.chars().last().map(|c| c.to_ascii_lowercase());
+        if !matches!(before, Some('a' | 'e' | 'i' | 'o' | 'u') | None) {
+            return format!("{}{}", stem, suffix("ies"));
+        }
+    }
+
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        return format!("{}{}", word, suffix("es"));
+    }
+
+    format!("{}{}", word, suffix("s"))
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "snake_case",
+    description = "Converts a string to snake_case.",
+    parsed(SnakeCaseFilter)
+)]
+pub struct SnakeCase;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "snake_case"]
+struct SnakeCaseFilter;
+
+impl Filter for SnakeCaseFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(input.to_kstr().to_snake_case()))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "kebab_case",
+    description = "Converts a string to kebab-case.",
+    parsed(KebabCaseFilter)
+)]
+pub struct KebabCase;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "kebab_case"]
+struct KebabCaseFilter;
+
+impl Filter for KebabCaseFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(input.to_kstr().to_kebab_case()))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "pascal_case",
+    description = "Converts a string to PascalCase.",
+    parsed(PascalCaseFilter)
+)]
+pub struct PascalCase;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "pascal_case"]
+struct PascalCaseFilter;
+
+impl Filter for PascalCaseFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(input.to_kstr().to_upper_camel_case()))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "pluralize",
+    description = "Returns the English plural of a word.",
+    parsed(PluralizeFilter)
+)]
+pub struct Pluralize;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "pluralize"]
+struct PluralizeFilter;
+
+impl Filter for PluralizeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(pluralize(&input.to_kstr())))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct IndentArgs {
+    #[parameter(
+        description = "Number of spaces to prepend to each line. Defaults to 2.",
+        arg_type = "integer"
+    )]
+    width: Option<Expression>,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "indent",
+    description = "Indents every non-empty line of a string.",
+    parameters(IndentArgs),
+    parsed(IndentFilter)
+)]
+pub struct Indent;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "indent"]
+struct IndentFilter {
+    #[parameters]
+    args: IndentArgs,
+}
+
+impl Filter for IndentFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let padding = " ".repeat(args.width.unwrap_or(2).max(0) as usize);
+
+        let indented = input
+            .to_kstr()
+            .split('\n')
+            .map(|line| {
+                if line.trim().is_empty() {
+                    line.to_string()
+                } else {
+                    format!("{}{}", padding, line)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(Value::scalar(indented))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "json",
+    description = "Encodes a value as JSON.",
+    parsed(JsonFilter)
+)]
+pub struct Json;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "json"]
+struct JsonFilter;
+
+impl Filter for JsonFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        serde_json::to_string(&input.to_value())
+            .map(Value::scalar)
+            .map_err(|e| liquid_core::Error::with_msg(format!("json filter failed: {}", e)))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "toml",
+    description = "Encodes a value as TOML.",
+    parsed(TomlFilter)
+)]
+pub struct Toml;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "toml"]
+struct TomlFilter;
+
+impl Filter for TomlFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        toml::Value::try_from(input.to_value())
+            .map(|value| Value::scalar(value.to_string()))
+            .map_err(|e| liquid_core::Error::with_msg(format!("toml filter failed: {}", e)))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sha256",
+    description = "Returns the hex encoded SHA-256 digest of a string.",
+    parsed(Sha256Filter)
+)]
+pub struct Sha256Hex;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "sha256"]
+struct Sha256Filter;
+
+impl Filter for Sha256Filter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        Ok(Value::scalar(sha256_hex(input.to_kstr().as_bytes())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "uuid",
+    description = "Derives a stable name-based (v5) UUID from a string.",
+    parsed(UuidFilter)
+)]
+pub struct Uuid;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "uuid"]
+struct UuidFilter;
+
+impl Filter for UuidFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        let id = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, input.to_kstr().as_bytes());
+        Ok(Value::scalar(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(src: &str) -> String {
+        let parser = parser(None).unwrap();
+        parser
+            .parse(src)
+            .unwrap()
+            .render(&liquid::Object::new())
+            .unwrap()
+    }
+
+    #[test]
+    fn case_filters_convert_identifiers() {
+        assert_eq!(
+            render("{{ 'MyProject name' | snake_case }}"),
+            "my_project_name"
+        );
+        assert_eq!(render("{{ 'MyProject' | kebab_case }}"), "my-project");
+        assert_eq!(
+            render("{{ 'my-project_name' | pascal_case }}"),
+            "MyProjectName"
+        );
+    }
+
+    #[test]
+    fn pluralize_handles_common_suffixes() {
+        assert_eq!(pluralize("user"), "users");
+        assert_eq!(pluralize("category"), "categories");
+        assert_eq!(pluralize("day"), "days");
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("match"), "matches");
+        assert_eq!(render("{{ 'class' | pluralize }}"), "classes");
+    }
+
+    #[test]
+    fn pluralize_follows_the_case_of_the_word() {
+        assert_eq!(pluralize("CITY"), "CITIES");
+        assert_eq!(pluralize("BOX"), "BOXES");
+        assert_eq!(pluralize("DAY"), "DAYS");
+        assert_eq!(pluralize("City"), "Cities");
+        assert_eq!(pluralize("HTTPProxy"), "HTTPProxies");
+    }
+
+    #[test]
+    fn indent_prefixes_non_empty_lines() {
+        assert_eq!(render("{{ 'a\n\nb' | indent }}"), "  a\n\n  b");
+        assert_eq!(render("{{ 'a' | indent: 4 }}"), "    a");
+    }
+
+    #[test]
+    fn json_and_toml_encode_values() {
+        assert_eq!(render("{{ 'say \"hi\"' | json }}"), r#""say \"hi\"""#);
+        assert_eq!(
+            render("{% assign xs = 'a,b' | split: ',' %}{{ xs | json }}"),
+            r#"["a","b"]"#
+        );
+        assert_eq!(render("{{ 'spark' | toml }}"), r#""spark""#);
+    }
+
+    #[test]
+    fn sha256_and_uuid_are_deterministic() {
+        assert_eq!(
+            render("{{ 'spark' | sha256 }}"),
+            "92f473809e5979a7da2b7f52771b3a9e3d7105dcb0f24ae333c5bf279b863d73"
+        );
+        assert_eq!(
+            render("{{ 'spark' | uuid }}"),
+            render("{{ 'spark' | uuid }}")
+        );
+        assert_eq!(render("{{ 'spark' | uuid }}").len(), 36);
+    }
+
+    #[test]
+    fn include_resolves_partials_from_template_dir() {
+        let dir = std::env::temp_dir().join("spark_test_liquid_partials");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(dir.join("header.liquid"), "# header").unwrap();
+        fs::write(dir.join("shared").join("footer.liquid"), "-- footer").unwrap();
+
+        let parser = parser(Some(&dir)).unwrap();
+        let output = parser
+            .parse(r#"{% include "header" %}|{% include "shared/footer.liquid" %}"#)
+            .unwrap()
+            .render(&liquid::Object::new())
+            .unwrap();
+        assert_eq!(output, "# header|-- footer");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn partials_are_read_when_included() {
+        let dir = std::env::temp_dir().join("spark_test_liquid_partials_lazy");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        let parser = parser(Some(&dir.join("nested"))).unwrap();
+
+        fs::write(dir.join("nested").join("late.liquid"), "late").unwrap();
+        let output = parser
+            .parse(r#"{% include "late" %}"#)
+            .unwrap()
+            .render(&liquid::Object::new())
+            .unwrap();
+        assert_eq!(output, "late");
+
+        fs::write(dir.join("outside.liquid"), "secret").unwrap();
+        let escaping = parser.parse(r#"{% include "../outside" %}"#).unwrap();
+        assert!(escaping.render(&liquid::Object::new()).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
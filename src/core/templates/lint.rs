@@ -0,0 +1,669 @@
+//! Static checks for templates (`spark lint`).
+//!
+//! [`Template::lint`] inspects a template without rendering it and returns a list
+//! of [`Diagnostic`]s. Positions are 1-based lines and columns in the template's
+//! TOML source when it was parsed with [`Template::from_str`] or
+//! [`Template::from_file`]; for templates built in code they are relative to the
+//! `path` or `content` of the offending `[[files]]` entry.
+//!
+//! Linting is strict: keys that [`Template::from_str`] would silently ignore are
+//! reported as `unknown-key` errors (see [`Template::unknown_keys`]).
+
+use crate::funcs::FunctionRegistry;
+use crate::templates::{filters, KEYWORDS_REGEX};
+use crate::{Fns, Keywords, OutputTarget, Result, Template};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Component;
+
+/// Variables Spark provides without the template defining them.
+pub const BUILTIN_VARIABLES: &[&str] = &[
+    "HOME",
+    "PROJECTNAME",
+    "CURRENTDIR",
+    "NOW",
+    "NOW_UTC",
+    "YYYY",
+    "YY",
+    "MM",
+    "DD",
+];
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The template will fail or misbehave when rendered.
+    Error,
+    /// The template renders, but probably not as intended.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A single problem found by [`Template::lint`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Error or warning.
+    pub severity: Severity,
+    /// Stable identifier of the check, e.g. `unknown-function`.
+    pub code: &'static str,
+    /// Human readable description.
+    pub message: String,
+    /// Index of the `[[files]]` entry the problem is in, if any.
+    pub file: Option<usize>,
+    /// 1-based line of the problem, if known.
+    pub line: Option<usize>,
+    /// 1-based column of the problem, if known.
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}: ", line, column)?,
+            (Some(line), None) => write!(f, "{}: ", line)?,
+            _ => {}
+        }
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// Where in the template a diagnostic points to, before it is mapped to a position.
+enum Anchor {
+    Template,
+    At(Option<(usize, usize)>),
+    Info,
+    Variable(String),
+    Path(usize),
+    Content(usize, usize),
+}
+
+impl Template {
+    /// Checks the template for problems without rendering it, using only the
+    /// built-in placeholder functions.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::Template;
+    ///
+    /// let template = Template::from_str(r#"
+    /// [[files]]
+    /// path = "../outside.txt"
+    /// content = "{{$NAME:shout}}"
+    /// "#)?;
+    ///
+    /// let codes: Vec<_> = template.lint()?.iter().map(|d| d.code).collect();
+    /// assert!(codes.contains(&"unknown-function"));
+    /// assert!(codes.contains(&"path-escape"));
+    /// # Ok::<(), spark::Error>(())
+    /// ```
+    pub fn lint(&self) -> Result<Vec<Diagnostic>> {
+        self.lint_with(&FunctionRegistry::default())
+    }
+
+    /// Like [`Template::lint`], but treats the functions in `functions` as known.
+    pub fn lint_with(&self, functions: &FunctionRegistry) -> Result<Vec<Diagnostic>> {
+        let re = Regex::new(KEYWORDS_REGEX)?;
+        let files = self.files.as_deref().unwrap_or_default();
+        let options = self.options.clone().unwrap_or_default();
+        let mut found = Vec::new();
+
+        if let Some(raw) = &self.raw {
+            for key in Self::unknown_keys(raw)? {
+                let mut message = format!("Unknown key '{}'", key.path);
+                if let Some(suggestion) = &key.suggestion {
+                    message.push_str(&format!(", did you mean '{}'?", suggestion));
+                }
+                found.push((
+                    Severity::Error,
+                    "unknown-key",
+                    message,
+                    Anchor::At(key.line.zip(key.column)),
+                ));
+            }
+        }
+
+        match &self.info {
+            None => found.push((
+                Severity::Warning,
+                "missing-info",
+                "Template has no [info] table".to_string(),
+                Anchor::Template,
+            )),
+            Some(info) => {
+                for (field, value) in [
+                    ("name", &info.name),
+                    ("description", &info.description),
+                    ("author", &info.author),
+                ] {
+                    if value.is_none() {
+                        found.push((
+                            Severity::Warning,
+                            "missing-info-field",
+                            format!("[info] is missing '{}'", field),
+                            Anchor::Info,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Every variable the template can produce on its own: generators, prompts,
+        // registered functions and [variables] defaults.
+        let mut defined: HashSet<String> =
+            BUILTIN_VARIABLES.iter().map(|v| v.to_string()).collect();
+        defined.extend(
+            self.variables
+                .iter()
+                .flatten()
+                .map(|(name, _)| name.clone()),
+        );
+        let mut referenced = HashSet::new();
+
+        for (index, file) in files.iter().enumerate() {
+            for (text, is_path) in [(&file.path, true), (&file.content, false)] {
+                for m in re.find_iter(text) {
+                    let stripped = Keywords::strip(m.as_str());
+                    if stripped.starts_with('.') {
+                        continue;
+                    }
+                    let anchor = if is_path {
+                        Anchor::Path(index)
+                    } else {
+                        Anchor::Content(index, m.start())
+                    };
+
+                    match stripped.split_once(':') {
+                        Some((name, spec)) => {
+                            referenced.insert(name.to_string());
+                            if Fns::parse(spec, functions).is_some() {
+                                defined.insert(name.to_string());
+                            } else {
+                                found.push((
+                                    Severity::Error,
+                                    "unknown-function",
+                                    format!("Unknown function '{}' in {}", spec, m.as_str()),
+                                    anchor,
+                                ));
+                            }
+                        }
+                        None => {
+                            referenced.insert(stripped);
+                        }
+                    }
+                }
+            }
+        }
+        for m in re.find_iter(&options.project_root) {
+            referenced.insert(Keywords::strip(m.as_str()));
+        }
+
+        let json_data = options.json_data.clone().unwrap_or(serde_json::Value::Null);
+        let mut reported = HashSet::new();
+        for (index, file) in files.iter().enumerate() {
+            for (text, is_path) in [(&file.path, true), (&file.content, false)] {
+                for m in re.find_iter(text) {
+                    let stripped = Keywords::strip(m.as_str());
+                    let anchor = if is_path {
+                        Anchor::Path(index)
+                    } else {
+                        Anchor::Content(index, m.start())
+                    };
+
+                    if stripped.starts_with('.') {
+                        if !json_data.is_null() {
+                            if let Err(e) = Fns::eval_json_filter(&stripped, &json_data) {
+                                found.push((
+                                    Severity::Error,
+                                    "json-path",
+                                    format!(
+                                        "{} does not resolve against options.json_data: {}",
+                                        m.as_str(),
+                                        e
+                                    ),
+                                    anchor,
+                                ));
+                            }
+                        }
+                        continue;
+                    }
+
+                    if stripped.contains(':') || defined.contains(&stripped) {
+                        continue;
+                    }
+                    if reported.insert(stripped.clone()) {
+                        found.push((
+                            Severity::Warning,
+                            "unresolved-placeholder",
+                            format!(
+                                "{} is never set by the template; it must come from --from, config keywords or the environment",
+                                m.as_str()
+                            ),
+                            anchor,
+                        ));
+                    }
+                }
+            }
+        }
+
+        for name in self.variables.iter().flatten().map(|(name, _)| name) {
+            if !referenced.contains(name) {
+                found.push((
+                    Severity::Warning,
+                    "unused-variable",
+                    format!("Variable '{}' is never used", name),
+                    Anchor::Variable(name.clone()),
+                ));
+            }
+        }
+
+        let mut paths = HashSet::new();
+        for (index, file) in files.iter().enumerate() {
+            if !paths.insert(file.path.as_str()) {
+                found.push((
+                    Severity::Error,
+                    "duplicate-path",
+                    format!("Output path '{}' is used by more than one file", file.path),
+                    Anchor::Path(index),
+                ));
+            }
+
+            if let OutputTarget::File(path) = OutputTarget::from_path(&file.path) {
+                let escapes = path.components().any(|c| {
+                    matches!(
+                        c,
+                        Component::ParentDir | Component::RootDir | Component::Prefix(_)
+                    )
+                });
+                if escapes || file.path.starts_with('~') {
+                    found.push((
+                        Severity::Warning,
+                        "path-escape",
+                        format!(
+                            "Output path '{}' is absolute or leaves the project directory",
+                            file.path
+                        ),
+                        Anchor::Path(index),
+                    ));
+                }
+            }
+        }
+
+        if options.use_liquid.unwrap_or(false) {
+            let parser = filters::parser(self.source_dir())?;
+            for (index, file) in files.iter().enumerate() {
+                let content = re.replace_all(&file.content, "spark");
+                if let Err(e) = parser.parse(&content) {
+                    found.push((
+                        Severity::Error,
+                        "liquid-syntax",
+                        format!("Invalid Liquid syntax: {}", e)
+                            .trim_end()
+                            .to_string(),
+                        Anchor::Content(index, 0),
+                    ));
+                }
+            }
+        }
+
+        let positions = Positions::new(self.raw.as_deref());
+        let mut diagnostics: Vec<Diagnostic> = found
+            .into_iter()
+            .map(|(severity, code, message, anchor)| {
+                let file = match anchor {
+                    Anchor::Path(index) | Anchor::Content(index, _) => Some(index),
+                    _ => None,
+                };
+                let (line, column) = positions.locate(&anchor, files).unzip();
+                Diagnostic {
+                    severity,
+                    code,
+                    message,
+                    file,
+                    line,
+                    column,
+                }
+            })
+            .collect();
+        diagnostics.sort_by_key(|d| (d.line.is_none(), d.line, d.column));
+
+        Ok(diagnostics)
+    }
+}
+
+#[derive(Deserialize)]
+struct SpannedTemplate {
+    files: Option<Vec<SpannedFile>>,
+}
+
+#[derive(Deserialize)]
+struct SpannedFile {
+    path: toml::Spanned<String>,
+    content: toml::Spanned<String>,
+}
+
+/// Maps [`Anchor`]s to line/column pairs in the TOML source.
+struct Positions<'a> {
+    raw: Option<&'a str>,
+    files: Vec<SpannedFile>,
+}
+
+impl<'a> Positions<'a> {
+    fn new(raw: Option<&'a str>) -> Self {
+        let files = raw
+            .and_then(|raw| toml::from_str::<SpannedTemplate>(raw).ok())
+            .and_then(|t| t.files)
+            .unwrap_or_default();
+        Self { raw, files }
+    }
+
+    fn locate(&self, anchor: &Anchor, files: &[crate::File]) -> Option<(usize, usize)> {
+        let Some(raw) = self.raw else {
+            return match anchor {
+                Anchor::Path(_) => Some((1, 1)),
+                Anchor::Content(index, offset) => {
+                    Some(line_column(&files.get(*index)?.content, *offset))
+                }
+                Anchor::At(position) => *position,
+                _ => None,
+            };
+        };
+
+        match anchor {
+            Anchor::Template => Some((1, 1)),
+            Anchor::At(position) => *position,
+            Anchor::Info => find_line(raw, |line| line == "[info]"),
+            Anchor::Variable(name) => find_line(raw, |line| {
+                line.split_once('=')
+                    .is_some_and(|(key, _)| key.trim().trim_matches(['"', '\'']) == name)
+            }),
+            Anchor::Path(index) => {
+                let start = self.files.get(*index)?.path.start();
+                Some(line_column(raw, start))
+            }
+            Anchor::Content(index, offset) => {
+                let span = &self.files.get(*index)?.content;
+                let mut start = span.start();
+                let rest = &raw[start..];
+                if rest.starts_with("'''") || rest.starts_with("\"\"\"") {
+                    start += 3;
+                    // TOML drops a newline directly after the opening delimiter
+                    if raw[start..].starts_with("\r\n") {
+                        start += 2;
+                    } else if raw[start..].starts_with('\n') {
+                        start += 1;
+                    }
+                } else {
+                    start += 1;
+                }
+
+                let content = files.get(*index).map_or("", |f| f.content.as_str());
+                let (line, column) = line_column(content, *offset);
+                let (base_line, base_column) = line_column(raw, start);
+                if line == 1 {
+                    Some((base_line, base_column + column - 1))
+                } else {
+                    Some((base_line + line - 1, column))
+                }
+            }
+        }
+    }
+}
+
+fn find_line(raw: &str, matches: impl Fn(&str) -> bool) -> Option<(usize, usize)> {
+    raw.lines().enumerate().find_map(|(index, line)| {
+        let trimmed = line.trim_start();
+        matches(trimmed.trim_end()).then(|| (index + 1, line.len() - trimmed.len() + 1))
+    })
+}
+
+/// 1-based line and column of the byte `offset` in `text`.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(template: &Template) -> Vec<&'static str> {
+        template.lint().unwrap().iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn clean_template_has_no_diagnostics() {
+        let template = Template::from_str(
+            r#"
+[info]
+name = "demo"
+description = "demo"
+author = "spark"
+
+[variables]
+LANG = "rust"
+
+[[files]]
+path = "{{$NAME:read}}/README.md"
+content = "{{$NAME}} in {{$LANG}} ({{$YYYY}})"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(template.lint().unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn reports_unknown_function_with_toml_position() {
+        let template = Template::from_str(
+            r#"[info]
+name = "demo"
+description = "demo"
+author = "spark"
+
+[[files]]
+path = "out.txt"
+content = '''
+first line
+  {{$NAME:shout}}
+'''
+"#,
+        )
+        .unwrap();
+
+        let diagnostics = template.lint().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unknown-function");
+        assert_eq!(diagnostics[0].file, Some(0));
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(10), Some(3))
+        );
+    }
+
+    #[test]
+    fn lint_with_accepts_registered_functions() {
+        let template = Template::from_str(
+            r#"
+[[files]]
+path = "out.txt"
+content = "{{$TOKEN:vault}}"
+"#,
+        )
+        .unwrap();
+        let functions = crate::Context::new()
+            .with_function("vault", |_, _| Ok(String::new()))
+            .functions()
+            .clone();
+
+        assert!(codes(&template).contains(&"unknown-function"));
+        assert!(!template
+            .lint_with(&functions)
+            .unwrap()
+            .iter()
+            .any(|d| d.code == "unknown-function"));
+    }
+
+    #[test]
+    fn reports_paths_info_and_variables() {
+        let template = Template::from_str(
+            r#"
+[info]
+name = "demo"
+
+[variables]
+UNUSED = "x"
+
+[[files]]
+path = "/etc/passwd"
+content = "{{$MYSTERY}}"
+
+[[files]]
+path = "/etc/passwd"
+content = "{{$MYSTERY}}"
+
+[[files]]
+path = "stdout://"
+content = ""
+"#,
+        )
+        .unwrap();
+
+        let diagnostics = template.lint().unwrap();
+        let found: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(
+            found.iter().filter(|c| **c == "missing-info-field").count(),
+            2
+        );
+        assert_eq!(found.iter().filter(|c| **c == "path-escape").count(), 2);
+        assert_eq!(
+            found
+                .iter()
+                .filter(|c| **c == "unresolved-placeholder")
+                .count(),
+            1
+        );
+        assert!(found.contains(&"duplicate-path"));
+
+        let unused = diagnostics
+            .iter()
+            .find(|d| d.code == "unused-variable")
+            .unwrap();
+        assert_eq!(unused.line, Some(6));
+    }
+
+    #[test]
+    fn partial_info_is_only_a_warning() {
+        let template = Template::from_str(
+            r#"
+[info]
+name = "demo"
+
+[[files]]
+path = "README.md"
+content = ""
+"#,
+        )
+        .unwrap();
+
+        let diagnostics = template.lint().unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.code == "missing-info-field" && d.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn reports_unknown_keys_from_toml_source() {
+        let template = Template::from_str(
+            r#"[info]
+name = "demo"
+description = "demo"
+author = "spark"
+
+[options]
+use_liqiud = false
+"#,
+        )
+        .unwrap();
+
+        let diagnostics = template.lint().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unknown-key");
+        assert_eq!(
+            diagnostics[0].message,
+            "Unknown key 'options.use_liqiud', did you mean 'use_liquid'?"
+        );
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(7), Some(1))
+        );
+    }
+
+    #[test]
+    fn reports_invalid_liquid_only_when_enabled() {
+        let toml = r#"
+[options]
+use_liquid = USE
+
+[[files]]
+path = "out.txt"
+content = "{% if x %}{{ \"{{$NAME:read}}\" | upcase }}"
+"#;
+
+        let enabled = Template::from_str(&toml.replace("USE", "true")).unwrap();
+        assert!(codes(&enabled).contains(&"liquid-syntax"));
+
+        let disabled = Template::from_str(&toml.replace("USE", "false")).unwrap();
+        assert!(!codes(&disabled).contains(&"liquid-syntax"));
+    }
+
+    #[test]
+    fn reports_bad_json_paths_against_embedded_data() {
+        let template = Template::from_str(
+            r#"
+[options]
+json_data = { user = { name = "Ada" } }
+
+[[files]]
+path = "out.txt"
+content = "{{$.user.name}} {{$.user.name | nope}}"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            codes(&template)
+                .iter()
+                .filter(|c| **c == "json-path")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn builder_templates_use_positions_relative_to_content() {
+        let template =
+            Template::builder().with_file(crate::File::create("a.txt", "x\n  {{$A:nope}}"));
+
+        let diagnostic = template
+            .lint()
+            .unwrap()
+            .into_iter()
+            .find(|d| d.code == "unknown-function")
+            .unwrap();
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(2), Some(3)));
+    }
+}
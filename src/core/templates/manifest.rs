@@ -0,0 +1,320 @@
+//! Generation manifest (`.spark/manifest.toml`) written into generated projects.
+//!
+//! The manifest records which template a project came from, the variable
+//! values it was rendered with and a hash of every generated file:
+//!
+//! ```toml
+//! spark_version = "2.0.0"
+//!
+//! [template]
+//! name = "service"
+//! source = "/home/me/.config/spark/templates/service.toml"
+//! hash = "sha256:9f86d0…"
+//!
+//! [variables]
+//! PROJECTNAME = "billing"
+//!
+//! [files]
+//! "src/main.rs" = "sha256:2c26b4…"
+//! ```
+//!
+//! A copy of the template is saved next to it as `.spark/template.toml`.
+//! File paths are relative to the directory holding `.spark/`: the project
+//! root when the template sets `project_root`, the output directory otherwise.
+//! Variables that look like secrets (see [`is_secret`]) are never recorded.
+
+use super::KEYWORDS_REGEX;
+use crate::funcs::sha256_hex;
+use crate::output_target::OutputTarget;
+use crate::transaction::Transaction;
+use crate::{Error, Keywords, Result, Template};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Location of the manifest relative to the project directory.
+pub const MANIFEST_PATH: &str = ".spark/manifest.toml";
+/// Copy of the template the project was generated from, used as the base of
+/// the three-way merge in `spark update`.
+pub const TEMPLATE_SNAPSHOT_PATH: &str = ".spark/template.toml";
+
+/// Words that mark a variable as a secret when they appear in its name.
+const SECRET_MARKERS: &[&str] = &[
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "PASSPHRASE",
+    "API_KEY",
+    "APIKEY",
+    "PRIVATE_KEY",
+    "CREDENTIAL",
+];
+
+/// Provenance of a generated project, stored at [`MANIFEST_PATH`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Version of Spark that generated the project.
+    pub spark_version: String,
+    /// Template the project was generated from.
+    pub template: TemplateRecord,
+    /// Values of the variables the template uses, secrets excluded.
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// `sha256:<hex>` digest of each generated file, keyed by relative path.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+/// The `[template]` table of a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateRecord {
+    /// `[info] name`, or the template file name without extension.
+    pub name: Option<String>,
+    /// Absolute path the template was loaded from.
+    pub source: Option<String>,
+    /// `sha256:<hex>` digest of the template TOML.
+    pub hash: String,
+}
+
+impl Manifest {
+    /// Starts a manifest for `template` rendered with `keywords`. Files are added
+    /// with [`Manifest::record_file`].
+    pub fn new(template: &Template, keywords: &HashMap<String, String>) -> Self {
+        let name = template
+            .info
+            .as_ref()
+            .and_then(|info| info.name.clone())
+            .or_else(|| {
+                template
+                    .source()
+                    .and_then(Path::file_stem)
+                    .map(|stem| stem.to_string_lossy().to_string())
+            });
+
+        let variables = template
+            .variable_names()
+            .into_iter()
+            .filter(|name| !is_secret(name))
+            .filter_map(|name| {
+                let value = keywords.get(&Keywords::from(&name, None))?;
+                Some((name, value.clone()))
+            })
+            .collect();
+
+        Self {
+            spark_version: env!("CARGO_PKG_VERSION").to_string(),
+            template: TemplateRecord {
+                name,
+                source: template.source().map(|source| {
+                    fs::canonicalize(source)
+                        .unwrap_or_else(|_| source.to_path_buf())
+                        .to_string_lossy()
+                        .to_string()
+                }),
+                hash: digest(template.content().as_bytes()),
+            },
+            variables,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Records the hash of a generated file's contents under `path`.
+    pub fn record_file(&mut self, path: impl Into<String>, contents: &[u8]) {
+        self.files.insert(path.into(), digest(contents));
+    }
+
+    /// Reads the manifest of the project in `project_dir`.
+    pub fn read(project_dir: &Path) -> Result<Self> {
+        let path = Self::path_in(project_dir);
+        let content = fs::read_to_string(&path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                Error::InvalidPath(path.clone())
+            } else {
+                Error::Io(e)
+            }
+        })?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Where the manifest of the project in `project_dir` lives.
+    pub fn path_in(project_dir: &Path) -> PathBuf {
+        project_dir.join(MANIFEST_PATH)
+    }
+
+    /// Reads the copy of the template saved next to the manifest.
+    pub fn read_template(project_dir: &Path) -> Result<Template> {
+        let path = project_dir.join(TEMPLATE_SNAPSHOT_PATH);
+        match fs::read_to_string(&path) {
+            Ok(content) => Template::from_str(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::InvalidPath(path)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the manifest and a copy of `template` into `project_dir/.spark/`.
+    pub(crate) fn save(
+        &self,
+        template: &Template,
+        project_dir: &Path,
+        transaction: &mut Transaction,
+    ) -> Result<()> {
+        let outputs = [
+            (MANIFEST_PATH, self.to_toml_string()?),
+            (TEMPLATE_SNAPSHOT_PATH, template.content()),
+        ];
+        for (path, content) in outputs {
+            transaction
+                .write(path, &OutputTarget::File(project_dir.join(path)), &content)
+                .map_err(|e| Error::OutputWrite {
+                    path: path.to_string(),
+                    message: e.to_string(),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Serializes the manifest as TOML.
+    pub fn to_toml_string(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Path of a rendered file relative to the project directory, or `None` for
+    /// files outside of `project_root` and absolute paths.
+    pub fn file_key(rendered_path: &str, project_root: &str) -> Option<String> {
+        let path = Path::new(rendered_path);
+        if path.has_root() || rendered_path.starts_with('~') {
+            return None;
+        }
+        let relative = path.strip_prefix(project_root.trim()).ok()?;
+        let key = relative.to_string_lossy().replace('\\', "/");
+        (!key.is_empty()).then_some(key)
+    }
+}
+
+/// Whether a variable is treated as a secret and kept out of the manifest: its
+/// name contains `SECRET`, `TOKEN`, `PASSWORD`, `API_KEY` or a similar word.
+pub fn is_secret(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// `sha256:<hex>` digest, the format used for hashes in the manifest.
+pub fn digest(data: &[u8]) -> String {
+    format!("sha256:{}", sha256_hex(data))
+}
+
+impl Template {
+    /// TOML source of the template: the original text when parsed from a string
+    /// or file, a serialization otherwise.
+    pub fn content(&self) -> String {
+        self.raw
+            .clone()
+            .unwrap_or_else(|| toml::to_string_pretty(self).unwrap_or_default())
+    }
+
+    /// Names of the variables the template uses (`NAME` for `{{$NAME}}` and
+    /// `{{$NAME:func}}`) plus those declared in `[variables]`, sorted. JSON paths
+    /// are not included.
+    pub fn variable_names(&self) -> Vec<String> {
+        let re = Regex::new(KEYWORDS_REGEX).expect("KEYWORDS_REGEX is valid");
+        let project_root = self
+            .options
+            .as_ref()
+            .map(|options| options.project_root.as_str());
+        let texts = self
+            .files
+            .iter()
+            .flatten()
+            .flat_map(|file| [file.path.as_str(), file.content.as_str()])
+            .chain(project_root);
+
+        let mut names: Vec<String> = texts
+            .flat_map(|text| re.find_iter(text))
+            .map(|m| Keywords::strip(m.as_str()))
+            .map(|name| name.split(':').next().unwrap_or_default().to_string())
+            .chain(
+                self.variables
+                    .iter()
+                    .flatten()
+                    .map(|(name, _)| name.clone()),
+            )
+            .filter(|name| !name.is_empty() && !name.contains('.'))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    #[test]
+    fn manifest_records_used_variables_without_secrets() {
+        let template = Template::builder()
+            .with_file(File::create(
+                "{{$NAME}}/.env",
+                "TOKEN={{$GITHUB_TOKEN}}\nID={{$ID:uuid}}\nOWNER={{$.owner}}",
+            ))
+            .with_source("/templates/service.toml");
+        let keywords = HashMap::from([
+            (Keywords::from("NAME", None), "billing".to_string()),
+            (Keywords::from("GITHUB_TOKEN", None), "ghp_x".to_string()),
+            (Keywords::from("ID", None), "42".to_string()),
+            (Keywords::from("HOME", None), "/home/me".to_string()),
+        ]);
+
+        let manifest = Manifest::new(&template, &keywords);
+
+        assert_eq!(manifest.template.name.as_deref(), Some("service"));
+        assert_eq!(
+            manifest.variables,
+            BTreeMap::from([
+                ("ID".to_string(), "42".to_string()),
+                ("NAME".to_string(), "billing".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn manifest_round_trips_through_toml() {
+        let template = Template::from_str("[[files]]\npath = \"a\"\ncontent = \"b\"\n").unwrap();
+        let mut manifest = Manifest::new(&template, &HashMap::new());
+        manifest.record_file("src/main.rs", b"fn main() {}");
+
+        let parsed: Manifest = toml::from_str(&manifest.to_toml_string().unwrap()).unwrap();
+        assert_eq!(parsed, manifest);
+        assert_eq!(
+            parsed.template.hash,
+            digest(b"[[files]]\npath = \"a\"\ncontent = \"b\"\n")
+        );
+    }
+
+    #[test]
+    fn file_key_is_relative_to_project_root() {
+        assert_eq!(
+            Manifest::file_key("demo/src/lib.rs", "demo").as_deref(),
+            Some("src/lib.rs")
+        );
+        assert_eq!(
+            Manifest::file_key("src/lib.rs", "").as_deref(),
+            Some("src/lib.rs")
+        );
+        assert_eq!(Manifest::file_key("other/lib.rs", "demo"), None);
+        assert_eq!(Manifest::file_key("/etc/hosts", ""), None);
+    }
+
+    #[test]
+    fn secrets_are_detected_by_name() {
+        assert!(is_secret("DB_PASSWORD"));
+        assert!(is_secret("stripe_api_key"));
+        assert!(!is_secret("PROJECTNAME"));
+    }
+}
@@ -1,24 +1,153 @@
+use crate::events::{Event, TerminalReporter};
 use crate::output_target::OutputTarget;
+use crate::transaction::Transaction;
 use crate::utils::*;
 use crate::*;
 use colored::Colorize;
+use manifest::Manifest;
 use promptly::prompt;
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+pub mod capture;
+pub mod filters;
+pub mod lint;
+pub mod manifest;
 pub mod options;
+pub mod plan;
+pub mod schema;
+pub mod snapshot;
+pub mod strict;
+pub mod update;
 
 pub const KEYWORDS_REGEX: &str = r"\{\{\$.*?\}\}";
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Template options (`[options]` table).
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(default)]
+#[schemars(deny_unknown_fields)]
 pub struct Options {
+    /// Initialize a git repository in the project directory.
     pub git: bool,
+    /// Render file contents with Liquid after placeholder replacement.
     pub use_liquid: Option<bool>,
+    /// JSON data for `{{$.path}}` placeholders.
     pub json_data: Option<serde_json::Value>,
+    /// Project directory, either a constant or `{{$PROJECTNAME}}` to ask for it.
     pub project_root: String,
 }
 
+/// A `[variables]` entry: either a default value (`LICENSE = "MIT"`), or a table
+/// saying how to ask for the variable (`PORT = { prompt = "Port", group = "Network" }`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Variable {
+    /// Default value, used when the context does not provide the variable.
+    Value(String),
+    /// Prompt settings, with an optional default.
+    Spec(VariableSpec),
+}
+
+impl Variable {
+    /// Value used when the context does not provide the variable.
+    pub fn default_value(&self) -> Option<&str> {
+        match self {
+            Self::Value(value) => Some(value),
+            Self::Spec(spec) => spec.default.as_deref(),
+        }
+    }
+
+    /// Prompt settings, if the entry is a table.
+    pub fn spec(&self) -> Option<&VariableSpec> {
+        match self {
+            Self::Value(_) => None,
+            Self::Spec(spec) => Some(spec),
+        }
+    }
+}
+
+impl From<String> for Variable {
+    fn from(value: String) -> Self {
+        Self::Value(value)
+    }
+}
+
+impl From<&str> for Variable {
+    fn from(value: &str) -> Self {
+        Self::Value(value.to_string())
+    }
+}
+
+// A plain string or a table. Written by hand rather than with `untagged` so
+// misspelled keys in the table are still reported by `Template::unknown_keys`.
+impl<'de> Deserialize<'de> for Variable {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct VariableVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for VariableVisitor {
+            type Value = Variable;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(
+                    "a default value or a table with default, prompt, help, example and group",
+                )
+            }
+
+            fn visit_str<E: serde::de::Error>(
+                self,
+                value: &str,
+            ) -> std::result::Result<Variable, E> {
+                Ok(Variable::Value(value.to_string()))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> std::result::Result<Variable, A::Error> {
+                VariableSpec::deserialize(serde::de::value::MapAccessDeserializer::new(map))
+                    .map(Variable::Spec)
+            }
+        }
+
+        deserializer.deserialize_any(VariableVisitor)
+    }
+}
+
+/// How a variable declared as a `[variables]` table is asked for.
+///
+/// A declared variable without a `default` is asked for when the template uses
+/// it. Declared variables are asked first, in the order of `[variables]` with
+/// the variables of a group kept together; the others follow in the order they
+/// appear in the template.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+#[schemars(deny_unknown_fields)]
+pub struct VariableSpec {
+    /// Value used when the context does not provide the variable; it is not
+    /// asked for then.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Question shown instead of the variable name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Explanation shown before the question.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    /// Example answer shown with the question.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,
+    /// Section the question is asked in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
 /// A rendered file containing its destination path and evaluated content.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RenderedFile {
@@ -57,12 +186,19 @@ impl Template {
     /// "#;
     /// let template = Template::from_str(toml).unwrap();
     /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(toml_str: &str) -> crate::Result<Self> {
         let template: Self = toml::from_str(toml_str)?;
-        Ok(template)
+        Ok(Self {
+            raw: Some(toml_str.to_string()),
+            ..template
+        })
     }
 
     /// Reads and parses a template from a TOML file path.
+    ///
+    /// The path is remembered so `{% include %}` can resolve Liquid partials
+    /// that live next to the template.
     pub fn from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
         let path_ref = path.as_ref();
         let content = fs::read_to_string(path_ref).map_err(|e| {
@@ -72,7 +208,7 @@ impl Template {
                 crate::Error::Io(e)
             }
         })?;
-        Self::from_str(&content)
+        Ok(Self::from_str(&content)?.with_source(path_ref))
     }
 
     /// Creates an empty `Template` builder.
@@ -80,10 +216,35 @@ impl Template {
         Self {
             info: None,
             options: None,
+            variables: None,
             files: Some(Vec::new()),
+            source: None,
+            raw: None,
         }
     }
 
+    /// Records the path the template was loaded from.
+    pub fn with_source(mut self, path: impl Into<PathBuf>) -> Self {
+        self.source = Some(path.into());
+        self
+    }
+
+    /// Returns the path the template was loaded from, if known.
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    /// Directory holding the template file; Liquid partials are resolved from here.
+    pub fn source_dir(&self) -> Option<&Path> {
+        self.source.as_deref().and_then(Path::parent).map(|dir| {
+            if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            }
+        })
+    }
+
     /// Adds a file entry to the template.
     pub fn with_file(mut self, file: File) -> Self {
         if let Some(ref mut files) = self.files {
@@ -106,6 +267,14 @@ impl Template {
         self
     }
 
+    /// Adds a `[variables]` entry, e.g. a default value from a `String`.
+    pub fn with_variable(mut self, name: impl Into<String>, variable: impl Into<Variable>) -> Self {
+        self.variables
+            .get_or_insert_with(IndexMap::new)
+            .insert(name.into(), variable.into());
+        self
+    }
+
     pub fn set_info(&mut self, info: Information) {
         self.info = Some(info);
     }
@@ -122,23 +291,19 @@ impl Template {
         self.options.clone()
     }
 
-    pub fn generate(dest: &str) -> std::result::Result<(), String> {
-        let files: Vec<File> = list_files(Path::new("./"))
-            .unwrap_or_default()
-            .into_iter()
-            .filter(|file| !file.contains(".git"))
-            .map(|file| {
-                File::new(
-                    file.replace("./", ""),
-                    fs::read_to_string(&file).unwrap_or_default(),
-                )
-            })
-            .collect();
+    /// Captures the current directory into a template at `dest` without replacing
+    /// anything; see [`Capture`](capture::Capture) for the interactive version.
+    /// The file written is reported to the context's reporter.
+    pub fn generate(dest: &str, context: &Context) -> std::result::Result<(), String> {
+        let mut capture = capture::Capture::scan(Path::new("."))
+            .map_err(|e| format!("Failed to read the current directory: {}", e))?;
+        capture
+            .files
+            .retain(|file| Path::new(&file.path) != Path::new(dest));
 
         let template = Self {
             info: None,
-            files: Some(files),
-            options: None,
+            ..capture.into_template(&[], Information::default())
         };
 
         let toml_string = toml::to_string_pretty(&template)
@@ -147,16 +312,25 @@ impl Template {
         write_content(dest, &toml_string)
             .map_err(|e| format!("Failed to write template to file: {}", e))?;
 
-        println!(
-            "{}: Template successfully generated at {}",
-            "Success".green().bold().blink(),
-            dest
-        );
+        let target = OutputTarget::File(PathBuf::from(dest));
+        context.emit(Event::file_written(dest, &target, &toml_string));
+        context.emit(Event::Info {
+            message: format!("Template successfully generated at {}", dest),
+        });
         Ok(())
     }
 
     pub fn liquify(string: &str) -> std::result::Result<String, liquid::Error> {
-        let parser = liquid::ParserBuilder::with_stdlib().build()?;
+        Self::liquify_with_partials(string, None)
+    }
+
+    /// Renders `string` with Spark's Liquid parser (stdlib plus the filters in
+    /// [`filters`]), resolving `{% include %}` partials from `partials_dir`.
+    pub fn liquify_with_partials(
+        string: &str,
+        partials_dir: Option<&Path>,
+    ) -> std::result::Result<String, liquid::Error> {
+        let parser = filters::parser(partials_dir)?;
         let empty_globals = liquid::Object::new();
 
         parser.parse(string)?.render(&empty_globals)
@@ -168,6 +342,17 @@ impl Template {
         options: &mut Options,
         file: &File,
         interactive: bool,
+    ) -> crate::Result<String> {
+        let context = Context::new().with_interactive(interactive);
+        Self::resolve_project_name_in(keywords, options, file, &context)
+    }
+
+    /// [`Template::resolve_project_name`] reporting the prompt to the context.
+    fn resolve_project_name_in(
+        keywords: &mut HashMap<String, String>,
+        options: &mut Options,
+        file: &File,
+        context: &Context,
     ) -> crate::Result<String> {
         let trimmed_content = file.content.trim();
         let trimmed_path = file.path.trim();
@@ -184,12 +369,17 @@ impl Template {
                 }
             }
 
-            if !interactive {
+            if !context.interactive {
                 return Err(crate::Error::MissingVariable("PROJECTNAME".to_string()));
             }
+            context.emit(Event::VariablePrompted {
+                name: "PROJECTNAME".to_string(),
+                help: None,
+            });
 
-            let project_name: String = prompt("Project name")
-                .map_err(|e| crate::Error::Prompt(e.to_string()))?;
+            let project_name: String =
+                prompt("Project name").map_err(|e| crate::Error::Prompt(e.to_string()))?;
+            context.emit(Event::answered("PROJECTNAME", &project_name));
 
             keywords.insert("{{$PROJECTNAME}}".to_string(), project_name.clone());
             options.set_project_root(&project_name);
@@ -205,8 +395,7 @@ impl Template {
         options: &mut Options,
         file: &File,
     ) -> std::result::Result<String, String> {
-        Self::resolve_project_name(keywords, options, file, true)
-            .map_err(|e| e.to_string())
+        Self::resolve_project_name(keywords, options, file, true).map_err(|e| e.to_string())
     }
 
     fn prepare_file_content(
@@ -214,12 +403,13 @@ impl Template {
         file_path: &str,
         keywords: &HashMap<String, String>,
         options: &Options,
-    ) -> std::result::Result<(String, String), String> {
+        partials_dir: Option<&Path>,
+    ) -> crate::Result<(String, String)> {
         let output = Keywords::replace_keywords(keywords, file_content);
         let path = Keywords::replace_keywords(keywords, file_path);
 
         let final_output = if options.use_liquid.unwrap_or(false) {
-            Self::liquify(&output).map_err(|e| format!("Liquid error: {}", e))?
+            Self::liquify_with_partials(&output, partials_dir)?
         } else {
             output
         };
@@ -227,48 +417,89 @@ impl Template {
         Ok((path, final_output))
     }
 
+    /// Variables from the context and its built-ins, with `[variables]` defaults
+    /// for the rest.
+    fn keywords_for(&self, context: &Context) -> HashMap<String, String> {
+        let mut keywords = context.render_keywords();
+        for (name, variable) in self.variables.iter().flatten() {
+            if let Some(default) = variable.default_value() {
+                keywords
+                    .entry(Keywords::from(name, None))
+                    .or_insert_with(|| default.to_string());
+            }
+        }
+        keywords
+    }
+
+    /// JSON data for `{{$.path}}` lookups: the context's, else the template's.
+    fn json_data_for(&self, context: &Context) -> serde_json::Value {
+        context
+            .json_data
+            .clone()
+            .or_else(|| self.options.as_ref().and_then(|o| o.json_data.clone()))
+            .unwrap_or(serde_json::Value::Null)
+    }
+
     /// Inner rendering pipeline. Returns rendered files **and** the fully-resolved
     /// keyword map so callers that need the resolved values (e.g. `extract`) can
-    /// obtain them without a second placeholder-scan pass.
+    /// obtain them without a second placeholder-scan pass, plus the options with
+    /// `project_root` resolved.
     fn render_inner(
         &self,
         context: &Context,
-    ) -> crate::Result<(Vec<RenderedFile>, HashMap<String, String>)> {
+    ) -> crate::Result<(Vec<RenderedFile>, HashMap<String, String>, Options)> {
         let re = Regex::new(KEYWORDS_REGEX)?;
         let options = self.options.clone().unwrap_or_default();
-        let json_data = context
-            .json_data
-            .clone()
-            .or_else(|| options.json_data.clone())
-            .unwrap_or(serde_json::Value::Null);
+        let json_data = self.json_data_for(context);
+        let mut keywords = self.keywords_for(context);
+
+        // Everything the template needs is asked for, or reported missing,
+        // before the first file renders.
+        let plan = self.plan_with(&keywords, &json_data, context)?;
+        plan.check(context)?;
+        plan.ask(&mut keywords, context)?;
 
-        let mut keywords = context.keywords.clone();
         let files = self.files.as_deref().unwrap_or_default();
         let mut rendered = Vec::with_capacity(files.len());
         let mut project = String::new();
         let mut active_options = options;
 
+        // Missing variables are collected across all files and reported together.
+        let mut missing = Vec::new();
+
         for file in files {
-            Fns::find_and_resolve(
+            if project.is_empty() {
+                match Self::resolve_project_name_in(
+                    &mut keywords,
+                    &mut active_options,
+                    file,
+                    context,
+                ) {
+                    Ok(name) => project = name,
+                    Err(crate::Error::MissingVariable(name)) => missing.push(name),
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Fns::resolve_collecting(
                 &format!("{}\n{}", file.content, file.path),
                 &mut keywords,
                 &re,
                 &json_data,
-                context.interactive,
+                context,
+                &mut missing,
             )?;
-
-            if project.is_empty() {
-                project = Self::resolve_project_name(
-                    &mut keywords,
-                    &mut active_options,
-                    file,
-                    context.interactive,
-                )?;
+            if !missing.is_empty() {
+                continue;
             }
 
-            let (path, final_output) =
-                Self::prepare_file_content(&file.content, &file.path, &keywords, &active_options)
-                    .map_err(crate::Error::Custom)?;
+            let (path, final_output) = Self::prepare_file_content(
+                &file.content,
+                &file.path,
+                &keywords,
+                &active_options,
+                self.source_dir(),
+            )?;
 
             rendered.push(RenderedFile {
                 path,
@@ -276,7 +507,14 @@ impl Template {
             });
         }
 
-        Ok((rendered, keywords))
+        if !missing.is_empty() {
+            return Err(crate::Error::missing_variables(missing));
+        }
+
+        active_options.project_root =
+            Keywords::replace_keywords(&keywords, &active_options.project_root);
+
+        Ok((rendered, keywords, active_options))
     }
 
     /// Pure in-memory rendering of the template with the provided [`Context`].
@@ -303,35 +541,154 @@ impl Template {
     /// assert_eq!(rendered[0].content, "Hello World!");
     /// ```
     pub fn render(&self, context: &Context) -> crate::Result<Vec<RenderedFile>> {
-        let (rendered, _keywords) = self.render_inner(context)?;
+        let (rendered, _keywords, _options) = self.render_inner(context)?;
         Ok(rendered)
     }
 
     /// Renders the template and writes all files to their target sinks (filesystem,
     /// `stdout://`, `stderr://`, or `clipboard://`), handling git repository initialization
     /// if enabled in template options.
+    ///
+    /// Relative paths, including `project_root`, are written under the context's
+    /// [output root](Context::with_output_root), or the current directory if none is set.
+    /// Unless the context is [trusted](Context::trusted), every file must resolve
+    /// inside that directory; otherwise nothing is written and
+    /// [`crate::Error::PathEscape`] is returned.
+    ///
+    /// Extraction is all-or-nothing for the filesystem: if a write or git
+    /// initialization fails, files and directories created so far, including a
+    /// new `.git`, are removed and overwritten files are restored before the
+    /// error is returned. Streams are written last, and a failing one leaves the
+    /// files in place.
+    ///
+    /// With [`Context::with_manifest`], a [`Manifest`] describing the generation is
+    /// written to `.spark/manifest.toml` in the project directory.
     pub fn extract_with_context(&self, context: &Context) -> crate::Result<Vec<RenderedFile>> {
-        let (rendered, _keywords) = self.render_inner(context)?;
-
-        for file in &rendered {
-            OutputTarget::from_path(&file.path)
-                .write(&file.content)
-                .map_err(|e| crate::Error::OutputWrite {
-                    path: file.path.clone(),
-                    message: e.to_string(),
-                })?;
+        let info = self.info.clone().unwrap_or_default();
+        context.emit(Event::TemplateLoaded {
+            name: info.name,
+            description: info.description,
+            author: info.author,
+            source: self
+                .source()
+                .map(|source| source.to_string_lossy().to_string()),
+        });
+
+        let (rendered, keywords, options) = self.render_inner(context)?;
+        let root = context.output_root().unwrap_or(Path::new(""));
+        for name in self.variable_names() {
+            let Some(value) = keywords.get(&Keywords::from(&name, None)) else {
+                continue;
+            };
+            let value = (!manifest::is_secret(&name)).then(|| value.clone());
+            context.emit(Event::VariableResolved { name, value });
         }
 
-        if let Some(options) = &self.options {
-            options.clone().handle();
+        if !context.trusted {
+            for file in &rendered {
+                OutputTarget::from_path(&file.path).ensure_within(root)?;
+            }
+            // The manifest and git repository go into `project_root`, which may
+            // be the output root itself.
+            let project_root = options.project_root.trim();
+            if !project_root.is_empty() {
+                let manifest = Path::new(project_root).join(manifest::MANIFEST_PATH);
+                OutputTarget::File(manifest)
+                    .ensure_within(root)
+                    .map_err(|e| match e {
+                        crate::Error::PathEscape { root, .. } => crate::Error::PathEscape {
+                            path: project_root.to_string(),
+                            root,
+                        },
+                        e => e,
+                    })?;
+            }
         }
 
+        // Filesystem outputs first so git init sees them, streams last so nothing
+        // is printed or copied for an extraction that ends up rolled back. A
+        // failing stream leaves the files in place: they were written correctly.
+        let (files, streams): (Vec<_>, Vec<_>) = rendered
+            .iter()
+            .map(|file| (OutputTarget::from_path(&file.path).under(root), file))
+            .partition(|(target, _)| target.expanded_path().is_some());
+
+        let mut transaction = Transaction::new(context.events.clone());
+        let result = files
+            .iter()
+            .try_for_each(|output| Self::write_output(&mut transaction, output))
+            .and_then(|()| match context.records_manifest() {
+                true => self.write_manifest(&mut transaction, &files, &keywords, &options, root),
+                false => Ok(()),
+            })
+            .and_then(|()| match self.options {
+                Some(_) => {
+                    if options.git && !options.project_root.is_empty() {
+                        transaction.record_dir(root.join(&options.project_root).join(".git"));
+                    }
+                    options.handle_in(root, context)
+                }
+                None => Ok(()),
+            });
+
+        if let Err(e) = result {
+            transaction.rollback();
+            return Err(e);
+        }
+
+        streams
+            .iter()
+            .try_for_each(|output| Self::write_output(&mut transaction, output))?;
+
         Ok(rendered)
     }
 
+    fn write_output(
+        transaction: &mut Transaction,
+        (target, file): &(OutputTarget, &RenderedFile),
+    ) -> crate::Result<()> {
+        transaction
+            .write(&file.path, target, &file.content)
+            .map_err(|e| crate::Error::OutputWrite {
+                path: file.path.clone(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Writes `.spark/manifest.toml` for the files just written, and a copy of the
+    /// template, into the project directory (`project_root` under `root`).
+    fn write_manifest(
+        &self,
+        transaction: &mut Transaction,
+        files: &[(OutputTarget, &RenderedFile)],
+        keywords: &HashMap<String, String>,
+        options: &Options,
+        root: &Path,
+    ) -> crate::Result<()> {
+        let mut manifest = Manifest::new(self, keywords);
+        for (target, file) in files {
+            let (Some(key), Some(path)) = (
+                Manifest::file_key(&file.path, &options.project_root),
+                target.expanded_path(),
+            ) else {
+                continue;
+            };
+            manifest.record_file(key, &fs::read(path)?);
+        }
+
+        manifest.save(self, &root.join(options.project_root.trim()), transaction)
+    }
+
     /// Legacy extraction method for backwards compatibility with CLI and tests.
-    pub fn extract(&mut self, keywords: &mut HashMap<String, String>) -> std::result::Result<(), String> {
-        let mut context = Context::from(keywords.clone());
+    /// Templates are trusted here, so paths outside the current directory are allowed.
+    /// Progress is printed with a [`TerminalReporter`].
+    pub fn extract(
+        &mut self,
+        keywords: &mut HashMap<String, String>,
+    ) -> std::result::Result<(), String> {
+        let mut context = Context::from(keywords.clone())
+            .trusted()
+            .with_reporter(TerminalReporter);
         if let Some(opts) = &self.options {
             if let Some(ref jd) = opts.json_data {
                 if !jd.is_null() && context.json_data.is_none() {
@@ -340,19 +697,20 @@ impl Template {
             }
         }
 
-        let (_rendered, resolved_keywords) = self
-            .render_inner(&context)
-            .map_err(|e| e.to_string())?;
+        let (_rendered, resolved_keywords, _options) =
+            self.render_inner(&context).map_err(|e| e.to_string())?;
 
         // Write outputs via the normal dispatch pipeline
         for file in &_rendered {
-            OutputTarget::from_path(&file.path)
-                .write(&file.content)
-                .map_err(|e| e.to_string())?;
+            let target = OutputTarget::from_path(&file.path);
+            target.write(&file.content).map_err(|e| e.to_string())?;
+            context.emit(Event::file_written(&file.path, &target, &file.content));
         }
 
         if let Some(options) = &self.options {
-            options.clone().handle();
+            if let Err(e) = options.clone().handle_in(Path::new(""), &context) {
+                context.emit(Event::error(&e));
+            }
         }
 
         // Copy resolved keyword values discovered during rendering back to the
@@ -365,17 +723,15 @@ impl Template {
         Ok(())
     }
 
-
+    /// Prints the template's `[info]` to stdout.
+    #[deprecated(note = "print `Information::summary` instead; the library does not print")]
     pub fn show_info(template: &Self) {
-        if let Some(information) = &template.info { println!(
-            "{}: {}\n{}: {}\n{}: {}\n",
-            "Name".yellow(),
-            information.name.as_ref().unwrap().bold().green(),
-            "Description".yellow(),
-            information.description.as_ref().unwrap().bold().green(),
-            "Author".yellow(),
-            information.author.as_ref().unwrap().bold().green()
-        ) }
+        if let Some(information) = &template.info {
+            for (label, value) in information.summary() {
+                println!("{}: {}", label.yellow(), value.bold().green());
+            }
+            println!();
+        }
     }
 }
 
@@ -385,6 +741,18 @@ mod tests {
     use std::collections::HashMap;
     use std::fs;
 
+    #[test]
+    #[allow(deprecated)]
+    fn show_info_allows_missing_fields() {
+        let template = Template::from_str("[info]\nname = \"x\"\n").unwrap();
+        let info = template.info.as_ref().unwrap();
+        assert_eq!(
+            info.summary(),
+            [("Name", "x"), ("Description", "-"), ("Author", "-")]
+        );
+        Template::show_info(&template);
+    }
+
     #[test]
     fn liquify_errors_on_unknown_variable() {
         let result = Template::liquify("Hello {{ name }}!");
@@ -403,18 +771,286 @@ mod tests {
         assert_eq!(output, "HELLO");
     }
 
+    #[test]
+    fn liquify_applies_spark_filters() {
+        let output = Template::liquify("{{ 'MyService' | snake_case | pluralize }}").unwrap();
+        assert_eq!(output, "my_services");
+    }
+
+    #[test]
+    fn render_uses_variable_defaults_unless_context_overrides() {
+        let template = Template::from_str(
+            r#"
+[variables]
+LANG = "rust"
+LICENSE = "MIT"
+
+[[files]]
+path = "out.txt"
+content = "{{$LANG}} / {{$LICENSE}}"
+"#,
+        )
+        .unwrap();
+
+        let ctx = Context::new()
+            .with_var("LICENSE", "Apache-2.0")
+            .non_interactive();
+        let rendered = template.render(&ctx).unwrap();
+        assert_eq!(rendered[0].content, "rust / Apache-2.0");
+    }
+
+    #[test]
+    fn extract_with_context_refuses_paths_outside_cwd() {
+        let outside = std::env::temp_dir().join("spark_test_sandbox_extract.txt");
+        let _ = fs::remove_file(&outside);
+        let template = Template::builder()
+            .with_file(File::create("stdout://", "harmless"))
+            .with_file(File::create("{{$NAME}}", "pwned"));
+        let ctx = Context::new()
+            .with_var("NAME", outside.to_string_lossy())
+            .non_interactive();
+
+        let err = template.extract_with_context(&ctx).unwrap_err();
+        assert!(matches!(err, crate::Error::PathEscape { .. }));
+        assert!(!outside.exists());
+
+        template.extract_with_context(&ctx.trusted()).unwrap();
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "pwned");
+
+        let _ = fs::remove_file(&outside);
+    }
+
+    #[test]
+    fn extract_with_context_writes_under_output_root() {
+        let root = std::env::temp_dir().join("spark_test_output_root");
+        let _ = fs::remove_dir_all(&root);
+        let template = Template::from_str(
+            r##"
+[options]
+git = true
+project_root = "{{$PROJECTNAME}}"
+
+[[files]]
+path = "{{$PROJECTNAME}}/README.md"
+content = "# {{$PROJECTNAME}}"
+"##,
+        )
+        .unwrap();
+        let ctx = Context::new()
+            .with_var("PROJECTNAME", "demo")
+            .with_output_root(&root)
+            .non_interactive();
+
+        template.extract_with_context(&ctx).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(root.join("demo/README.md")).unwrap(),
+            "# demo"
+        );
+        assert!(root.join("demo/.git").exists());
+        assert!(!Path::new("demo").exists());
+
+        let escaping = Template::builder().with_file(File::create("../escape.txt", "x"));
+        let err = escaping.extract_with_context(&ctx).unwrap_err();
+        assert!(matches!(err, crate::Error::PathEscape { .. }));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn extract_with_context_keeps_manifest_inside_output_root() {
+        let base = std::env::temp_dir().join("spark_test_manifest_escape");
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("root");
+        let mut options = Options::default();
+        options.set_project_root("{{$PROJECTNAME}}");
+        let template = Template::builder()
+            .with_options(options)
+            .with_file(File::create("stdout://", "done"));
+        let ctx = Context::new()
+            .with_var("PROJECTNAME", "../outside")
+            .with_output_root(&root)
+            .with_manifest(true)
+            .non_interactive();
+
+        let err = template.extract_with_context(&ctx).unwrap_err();
+        assert!(matches!(err, crate::Error::PathEscape { ref path, .. } if path == "../outside"));
+        assert!(!base.join("outside").exists());
+
+        // The output root itself is a valid project directory.
+        template
+            .extract_with_context(&ctx.with_var("PROJECTNAME", "."))
+            .unwrap();
+        assert!(root.join(manifest::MANIFEST_PATH).exists());
+
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn extract_with_context_rolls_back_on_write_failure() {
+        let root = std::env::temp_dir().join("spark_test_rollback_write");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("existing.txt"), "original").unwrap();
+
+        let template = Template::builder()
+            .with_file(File::create("existing.txt", "overwritten"))
+            .with_file(File::create("app/src/main.rs", "fn main() {}"))
+            .with_file(File::create(
+                "app/src/main.rs/oops.txt",
+                "cannot nest in a file",
+            ));
+        let ctx = Context::new().with_output_root(&root).non_interactive();
+
+        let err = template.extract_with_context(&ctx).unwrap_err();
+        assert!(
+            matches!(err, crate::Error::OutputWrite { ref path, .. } if path.ends_with("oops.txt"))
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("existing.txt")).unwrap(),
+            "original"
+        );
+        assert!(!root.join("app").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn extract_with_context_rolls_back_on_git_failure() {
+        let root = std::env::temp_dir().join("spark_test_rollback_hook");
+        let _ = fs::remove_dir_all(&root);
+        let mut options = Options::default();
+        options.set_git(true);
+        options.set_project_root("not-generated");
+        let template = Template::builder()
+            .with_options(options)
+            .with_file(File::create("app/README.md", "# app"));
+        let ctx = Context::new().with_output_root(&root).non_interactive();
+
+        let err = template.extract_with_context(&ctx).unwrap_err();
+        assert!(matches!(err, crate::Error::Hook(_)));
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn extract_with_context_records_manifest_when_enabled() {
+        let root = std::env::temp_dir().join("spark_test_manifest");
+        let _ = fs::remove_dir_all(&root);
+        let mut options = Options::default();
+        options.set_project_root("{{$PROJECTNAME}}");
+        let template = Template::builder()
+            .with_options(options)
+            .with_file(File::create(
+                "{{$PROJECTNAME}}/.env",
+                "TOKEN={{$API_TOKEN}}",
+            ))
+            .with_file(File::create("stdout://", "done"));
+        let ctx = Context::new()
+            .with_var("PROJECTNAME", "demo")
+            .with_var("API_TOKEN", "hunter2")
+            .with_output_root(&root)
+            .non_interactive();
+
+        template.extract_with_context(&ctx).unwrap();
+        assert!(!root.join("demo").join(manifest::MANIFEST_PATH).exists());
+
+        let _ = fs::remove_dir_all(&root);
+        template
+            .extract_with_context(&ctx.with_manifest(true))
+            .unwrap();
+        let manifest = Manifest::read(&root.join("demo")).unwrap();
+
+        assert_eq!(manifest.spark_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            manifest.template.hash,
+            manifest::digest(template.content().as_bytes())
+        );
+        assert_eq!(
+            manifest.variables.get("PROJECTNAME").map(String::as_str),
+            Some("demo")
+        );
+        assert!(!manifest.variables.contains_key("API_TOKEN"));
+        assert_eq!(manifest.files.keys().collect::<Vec<_>>(), [".env"],);
+        assert_eq!(manifest.files[".env"], manifest::digest(b"TOKEN=hunter2"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn strict_render_reports_every_missing_variable() {
+        let template = Template::builder()
+            .with_file(File::create("{{$DIR}}/a.txt", "{{$A}} {{$.db.port}}"))
+            .with_file(File::create("b.txt", "{{$B}} {{$A}} {{$.db.host}}"));
+        let context = Context::new()
+            .with_json(serde_json::json!({ "db": { "host": "localhost" } }))
+            .with_var("B", "b")
+            .non_interactive();
+
+        let rendered = template.render(&context).unwrap();
+        assert_eq!(rendered[0].content, " null");
+
+        let err = template.render(&context.strict()).unwrap_err();
+        assert!(
+            matches!(err, crate::Error::MissingVariables(ref names) if names == &["A", ".db.port", "DIR"])
+        );
+        assert_eq!(
+            err.to_string(),
+            "Missing required variables: A, .db.port, DIR"
+        );
+    }
+
+    #[test]
+    fn render_errors_on_unknown_function() {
+        let template = Template::builder().with_file(File::create("out.txt", "{{$NAME:shout}}"));
+
+        let err = template
+            .render(&Context::new().non_interactive())
+            .unwrap_err();
+        assert!(matches!(err, crate::Error::UnknownFunction(ref f) if f == "shout"));
+    }
+
+    #[test]
+    fn render_resolves_partials_next_to_template_file() {
+        let dir = std::env::temp_dir().join("spark_test_render_partials");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("license.liquid"), "MIT License").unwrap();
+        let template_path = dir.join("demo.toml");
+        fs::write(
+            &template_path,
+            r#"
+[[files]]
+path = "LICENSE"
+content = '{% include "license" %} ({{ "{{$NAME}}" | kebab_case }})'
+"#,
+        )
+        .unwrap();
+
+        let template = Template::from_file(&template_path).unwrap();
+        assert_eq!(template.source_dir(), Some(dir.as_path()));
+
+        let context = Context::new().with_var("NAME", "MyApp").non_interactive();
+        let rendered = template.render(&context).unwrap();
+        assert_eq!(rendered[0].content, "MIT License (my-app)");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn set_info_files_and_options() {
         let mut template = Template {
             info: None,
             options: None,
             files: None,
+            ..Default::default()
         };
 
         template.set_info(Information {
             name: Some("demo".into()),
             author: Some("author".into()),
             description: Some("desc".into()),
+            ..Default::default()
         });
         template.set_files(vec![File::new("a.txt".into(), "hi".into())]);
         template.set_options(Options {
@@ -457,7 +1093,7 @@ mod tests {
         };
 
         let (path, content) =
-            Template::prepare_file_content("Hello {{$TEST}}", "out.txt", &keywords, &options)
+            Template::prepare_file_content("Hello {{$TEST}}", "out.txt", &keywords, &options, None)
                 .unwrap();
 
         assert_eq!(path, "out.txt");
@@ -479,6 +1115,7 @@ mod tests {
             "out.txt",
             &keywords,
             &options,
+            None,
         )
         .unwrap();
 
@@ -504,6 +1141,7 @@ mod tests {
             "out.txt",
             &keywords,
             &options,
+            None,
         )
         .unwrap();
 
@@ -529,6 +1167,7 @@ mod tests {
                 // JSON resolve → keyword replace → Liquid filter
                 r#"{% for i in (1..3) %}{{ "{{$.name}}" | upcase }}-{{ i }} {% endfor %}"#.into(),
             )]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -556,9 +1195,14 @@ mod tests {
             project_root: String::new(),
         };
 
-        let (path, content) =
-            Template::prepare_file_content("hi", &file_path.to_string_lossy(), &keywords, &options)
-                .unwrap();
+        let (path, content) = Template::prepare_file_content(
+            "hi",
+            &file_path.to_string_lossy(),
+            &keywords,
+            &options,
+            None,
+        )
+        .unwrap();
 
         assert_eq!(path, file_path.to_string_lossy());
         assert_eq!(content, "hi");
@@ -582,6 +1226,7 @@ mod tests {
                 project_root: String::new(),
             }),
             files: Some(vec![]),
+            ..Default::default()
         };
         let mut keywords = HashMap::new();
         assert!(template.extract(&mut keywords).is_ok());
@@ -606,6 +1251,7 @@ mod tests {
                 out_file.to_string_lossy().to_string(),
                 "Hello {{$NAME}}".into(),
             )]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -644,6 +1290,7 @@ mod tests {
                 File::new(first_path, "first".into()),
                 File::new(second_path, "second {{$.module}}".into()),
             ]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -877,6 +1524,7 @@ content = "hello"
                 project_root: "{{$PROJECTNAME}}".into(),
             }),
             files: Some(vec![File::new(file_path, "# {{$PROJECTNAME}}".into())]),
+            ..Default::default()
         };
 
         // Pre-populate PROJECTNAME exactly as main() does when --from is given.
@@ -974,6 +1622,7 @@ Status: {{{{$.status[0]}}}}
                 "stdout://".into(),
                 "Hello {{$GREETING}}".into(),
             )]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -1004,6 +1653,7 @@ Status: {{{{$.status[0]}}}}
                 "stderr://".into(),
                 "error: {{$MSG}}".into(),
             )]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -1034,6 +1684,7 @@ Status: {{{{$.status[0]}}}}
                 project_root: String::new(),
             }),
             files: Some(vec![File::new(raw_path, "content via file://".into())]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -1067,6 +1718,7 @@ Status: {{{{$.status[0]}}}}
                 "stdout://".into(),
                 "project: {{$NAME}} by {{$AUTHOR}}".into(),
             )]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -1091,6 +1743,7 @@ Status: {{{{$.status[0]}}}}
                 "stderr://".into(),
                 "{{ 'warn' | upcase }}".into(),
             )]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -1123,6 +1776,7 @@ Status: {{{{$.status[0]}}}}
                 File::new("stdout://".into(), "stdout content".into()),
                 File::new("stderr://".into(), "stderr content".into()),
             ]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
@@ -1162,6 +1816,7 @@ Status: {{{{$.status[0]}}}}
             // content ends with "{{$" and path starts with a string ending in "}}"
             // Neither is a valid placeholder on its own.
             files: Some(vec![File::new(file_path.clone(), "Hello {{$".into())]),
+            ..Default::default()
         };
 
         let mut keywords = HashMap::new();
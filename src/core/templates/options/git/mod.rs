@@ -1,4 +1,6 @@
-use colored::*;
+use crate::events::Event;
+use crate::Context;
+use std::path::Path;
 use std::process::Command;
 
 pub fn check_git() -> Result<(), String> {
@@ -9,47 +11,77 @@ pub fn check_git() -> Result<(), String> {
     Ok(())
 }
 
-pub fn init(project_root: &str) {
-    if let Err(e) = check_git() {
-        eprintln!("{}: {}", "error".red().bold(), e.red().bold());
-        return;
-    }
+/// Value of a git configuration key such as `user.name`, if git is installed and it is set.
+pub fn config_value(key: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .output()
+        .ok()?;
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
 
-    if let Err(e) = std::env::set_current_dir(project_root) {
-        eprintln!(
-            "{}: {}",
-            "error".red().bold(),
-            format!("Failed to change directory: {}", e).red().bold()
-        );
-        return;
+/// Runs `git init` in `project_root`, reporting the outcome to the context's
+/// reporter. Use [`init_in`] to handle the result yourself.
+pub fn init(project_root: &str, context: &Context) {
+    let message = match init_in(Path::new(project_root)) {
+        Ok(true) => "Git initialized successfully.",
+        Ok(false) => "Git is already initialized.",
+        Err(e) => return context.emit(Event::error(&crate::Error::Hook(e))),
+    };
+    context.emit(Event::HookFinished {
+        hook: "git init".to_string(),
+        dir: project_root.to_string(),
+        message: message.to_string(),
+    });
+}
+
+/// Runs `git init` inside `dir` without changing the process working directory.
+/// Returns `false` without doing anything if `dir` already is a git repository.
+pub fn init_in(dir: &Path) -> Result<bool, String> {
+    check_git()?;
+
+    if !dir.is_dir() {
+        return Err(format!("Project root {} is not a directory", dir.display()));
     }
 
-    if std::path::Path::new(".git").exists() {
-        println!("{}", "\n✅ Git is already initialized.".yellow().bold());
-        return;
+    if dir.join(".git").exists() {
+        return Ok(false);
     }
 
-    match Command::new("git").arg("init").status() {
-        Ok(status) if status.success() => {
-            println!("{}", "\n✅ Git initialized successfully.".green().bold());
-        }
-        _ => {
-            eprintln!(
-                "{}: {}",
-                "error".red().bold(),
-                "Git initialization failed.".red().bold()
-            );
-        }
+    // Output is captured so it cannot interleave with Spark's own (e.g. JSON) output.
+    match Command::new("git").arg("init").current_dir(dir).output() {
+        Ok(output) if output.status.success() => Ok(true),
+        _ => Err("Git initialization failed.".to_string()),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::check_git;
+    use super::{check_git, init_in};
 
     #[test]
     fn check_git_succeeds_when_git_is_available() {
         // This environment has git installed; the call should succeed.
         assert!(check_git().is_ok());
     }
+
+    #[test]
+    fn init_in_does_not_change_current_dir() {
+        let dir = std::env::temp_dir().join(format!("spark-git-init-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cwd = std::env::current_dir().unwrap();
+
+        init_in(&dir).unwrap();
+
+        assert!(dir.join(".git").exists());
+        assert_eq!(std::env::current_dir().unwrap(), cwd);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn init_in_fails_for_missing_directory() {
+        let dir = std::env::temp_dir().join("spark-git-init-missing");
+        assert!(init_in(&dir).is_err());
+    }
 }
@@ -1,5 +1,6 @@
-use crate::Options;
-use colored::*;
+use crate::events::Event;
+use crate::{Context, Options};
+use std::path::Path;
 pub mod git;
 
 impl Default for Options {
@@ -26,30 +27,53 @@ impl Options {
         self.project_root = project_root.to_string();
     }
 
-    pub fn handle(self) {
+    /// Runs the post-generation steps, reporting progress and errors to the
+    /// context's reporter.
+    pub fn handle(self, context: &Context) {
+        if let Err(e) = self.handle_in(Path::new(""), context) {
+            context.emit(Event::error(&e));
+        }
+    }
+
+    /// Like [`Options::handle`], but resolves a relative `project_root` against `root`,
+    /// reports progress through the context's events and returns
+    /// [`crate::Error::Hook`] if git initialization fails.
+    pub fn handle_in(self, root: &Path, context: &Context) -> crate::Result<()> {
         if self.git {
             if self.project_root.is_empty() {
-                eprintln!(
-                    "\n{}: {}",
-                    "error".to_string().red(),
-                    "Project root is not set".yellow()
-                );
-                return;
+                context.emit(Event::Warning {
+                    message: "Project root is not set, skipping git init".to_string(),
+                });
+                return Ok(());
             }
 
-            println!(
-                "\nInitializing git repository for {}\n",
-                self.project_root.blue()
-            );
+            let dir = root.join(&self.project_root);
+            let (hook, dir_name) = ("git init".to_string(), dir.display().to_string());
+            context.emit(Event::HookStarted {
+                hook: hook.clone(),
+                dir: dir_name.clone(),
+            });
 
-            git::init(&self.project_root);
+            let initialized = git::init_in(&dir).map_err(crate::Error::Hook)?;
+            let message = if initialized {
+                "Git initialized successfully."
+            } else {
+                "Git is already initialized."
+            };
+            context.emit(Event::HookFinished {
+                hook,
+                dir: dir_name,
+                message: message.to_string(),
+            });
         }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn default_options() {
@@ -81,7 +105,7 @@ mod tests {
             project_root: String::new(),
         };
         // Should return without attempting git init.
-        options.handle();
+        options.handle(&Context::new());
     }
 
     #[test]
@@ -92,7 +116,15 @@ mod tests {
             json_data: None,
             project_root: String::new(),
         };
-        // Should print an error and return without panicking.
-        options.handle();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let context = Context::new()
+            .with_reporter(move |event: &Event| sink.lock().unwrap().push(event.clone()));
+
+        options.handle(&context);
+        assert!(matches!(
+            events.lock().unwrap().as_slice(),
+            [Event::Warning { .. }]
+        ));
     }
 }
@@ -0,0 +1,338 @@
+//! Working out which values a template needs before anything is rendered.
+//!
+//! [`Template::plan`] scans all files of a template at once and lists the
+//! variables the user would be asked for: `:read` placeholders, the project name
+//! and the sources of transforms such as `{{$X:sha256}}`. In
+//! [strict](Context::strict) mode it also lists placeholders that have no value
+//! and cannot be asked for.
+//!
+//! Rendering asks for every input in a single questionnaire before the first
+//! file is rendered. A context that cannot prompt fails instead, naming every
+//! missing variable at once.
+//!
+//! Variables declared as tables in `[variables]` (see [`VariableSpec`]) are
+//! asked first, in declaration order with each group kept together, and show
+//! their prompt, help text and example. Declared variables without a default are
+//! asked for like `:read` placeholders.
+
+use super::{Template, VariableSpec, KEYWORDS_REGEX};
+use crate::events::Event;
+use crate::{Context, Error, Fns, Keywords, Result};
+use promptly::prompt;
+use regex::Regex;
+use std::collections::HashMap;
+
+const PROJECTNAME: &str = "{{$PROJECTNAME}}";
+
+/// A variable the user is asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    /// Variable name without `{{$` and `}}`, e.g. `PROJECTNAME`.
+    pub name: String,
+    /// How to ask, from the variable's `[variables]` table.
+    pub spec: VariableSpec,
+}
+
+impl Input {
+    /// Text shown when asking for the value.
+    pub fn label(&self) -> &str {
+        match (&self.spec.prompt, self.name.as_str()) {
+            (Some(prompt), _) => prompt,
+            (None, "PROJECTNAME") => "Project name",
+            (None, name) => name,
+        }
+    }
+
+    /// The label with the example, if there is one: `Port (e.g. 8080)`.
+    fn question(&self) -> String {
+        match &self.spec.example {
+            Some(example) => format!("{} (e.g. {})", self.label(), example),
+            None => self.label().to_string(),
+        }
+    }
+}
+
+/// The values a template needs that its context does not provide.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// Variables to ask for, in the order they are asked.
+    pub inputs: Vec<Input>,
+    /// Placeholders without a value that cannot be asked for, including failed
+    /// JSON lookups. Only collected in strict mode; otherwise they render empty.
+    pub missing: Vec<String>,
+}
+
+impl Plan {
+    /// Whether the template can render without asking for anything.
+    pub fn is_complete(&self) -> bool {
+        self.inputs.is_empty() && self.missing.is_empty()
+    }
+
+    /// The error rendering fails with when the plan cannot be carried out by
+    /// `context`: missing variables in strict mode, and inputs when it cannot
+    /// prompt.
+    pub(crate) fn check(&self, context: &Context) -> Result<()> {
+        let mut missing: Vec<String> = Vec::new();
+        if !context.interactive {
+            missing.extend(self.inputs.iter().map(|input| input.name.clone()));
+        }
+        missing.extend(self.missing.iter().cloned());
+
+        match missing.is_empty() {
+            true => Ok(()),
+            false => Err(Error::missing_variables(missing)),
+        }
+    }
+
+    /// Asks for every input in order and adds the answers to `keywords`.
+    pub(crate) fn ask(
+        &self,
+        keywords: &mut HashMap<String, String>,
+        context: &Context,
+    ) -> Result<()> {
+        let mut group = None;
+        for input in &self.inputs {
+            if input.spec.group.is_some() && input.spec.group != group {
+                group = input.spec.group.clone();
+                context.emit(Event::PromptGroup {
+                    name: group.clone().unwrap_or_default(),
+                });
+            }
+
+            context.emit(Event::VariablePrompted {
+                name: input.name.clone(),
+                help: input.spec.help.clone(),
+            });
+            let value: String =
+                prompt(input.question()).map_err(|e| Error::Prompt(e.to_string()))?;
+            context.emit(Event::answered(&input.name, &value));
+            keywords.insert(Keywords::from(&input.name, None), value);
+        }
+        Ok(())
+    }
+}
+
+impl Template {
+    /// Lists the values this template needs that `context` does not provide,
+    /// without prompting or rendering anything.
+    ///
+    /// # Example
+    /// ```rust
+    /// use spark::{Context, File, Template};
+    ///
+    /// let template = Template::builder()
+    ///     .with_file(File::create("{{$PROJECTNAME}}/README.md", "{{$DESC:read}} {{$TOKEN:sha256}}"));
+    ///
+    /// let plan = template.plan(&Context::new())?;
+    /// let names: Vec<_> = plan.inputs.iter().map(|input| input.name.as_str()).collect();
+    /// assert_eq!(names, ["PROJECTNAME", "DESC", "TOKEN"]);
+    /// # Ok::<(), spark::Error>(())
+    /// ```
+    pub fn plan(&self, context: &Context) -> Result<Plan> {
+        self.plan_with(
+            &self.keywords_for(context),
+            &self.json_data_for(context),
+            context,
+        )
+    }
+
+    /// [`Template::plan`] against already resolved keywords and JSON data.
+    pub(crate) fn plan_with(
+        &self,
+        keywords: &HashMap<String, String>,
+        json_data: &serde_json::Value,
+        context: &Context,
+    ) -> Result<Plan> {
+        let re = Regex::new(KEYWORDS_REGEX)?;
+        let text: String = self
+            .files
+            .iter()
+            .flatten()
+            .map(|file| format!("{}\n{}\n", file.content, file.path))
+            .collect();
+        let found = Fns::find_checked(&text, keywords, &re, &context.functions)?;
+        let mut plan = Plan::default();
+        let mut inputs: Vec<&str> = Vec::new();
+
+        let project_root = self
+            .options
+            .as_ref()
+            .map_or("", |options| options.project_root.as_str());
+        let has_project_name = keywords
+            .get(PROJECTNAME)
+            .is_some_and(|name| !name.is_empty());
+        if (text.contains(PROJECTNAME) || project_root.contains(PROJECTNAME)) && !has_project_name {
+            inputs.push("PROJECTNAME");
+        }
+
+        for (name, (_keyword, function)) in &found {
+            if function.is_transform() {
+                // Transforms are asked for their source, unless another
+                // placeholder produces it.
+                let source = name
+                    .split_once(':')
+                    .map_or(name.as_str(), |(source, _)| source);
+                if !keywords.contains_key(&Keywords::from(source, None))
+                    && !found.contains_key(source)
+                {
+                    inputs.push(source);
+                }
+                continue;
+            }
+            if keywords.contains_key(&Keywords::from(name, None)) {
+                continue;
+            }
+
+            if !json_data.is_null() && name.contains('.') {
+                if context.strict && !matches!(Fns::eval_json_lookup(name, json_data), Ok(Some(_)))
+                {
+                    plan.missing.push(name.clone());
+                }
+                continue;
+            }
+
+            match function {
+                Fns::Read => inputs.push(name),
+                Fns::None if name == "PROJECTNAME" => {}
+                Fns::None if self.declared(name).is_some() => inputs.push(name),
+                Fns::None if context.strict => plan.missing.push(name.clone()),
+                _ => {}
+            }
+        }
+
+        plan.inputs = self.questionnaire(inputs);
+        Ok(plan)
+    }
+
+    /// Prompt settings of a variable declared as a `[variables]` table.
+    fn declared(&self, name: &str) -> Option<&VariableSpec> {
+        self.variables.as_ref()?.get(name)?.spec()
+    }
+
+    /// Orders `names` for asking: declared variables first, in declaration order
+    /// with each group moved up to its first member, then the others as found.
+    fn questionnaire(&self, mut names: Vec<&str>) -> Vec<Input> {
+        let declared: Vec<(&String, &VariableSpec)> = self
+            .variables
+            .iter()
+            .flatten()
+            .filter_map(|(name, variable)| Some((name, variable.spec()?)))
+            .collect();
+        let rank = |name: &str| {
+            let Some(index) = declared.iter().position(|(declared, _)| *declared == name) else {
+                return (1, 0, 0);
+            };
+            let group = declared[index].1.group.as_ref();
+            let first = declared
+                .iter()
+                .position(|(_, spec)| group.is_some() && spec.group.as_ref() == group)
+                .unwrap_or(index);
+            (0, first, index)
+        };
+
+        let mut seen = Vec::new();
+        names.retain(|name| {
+            let new = !seen.contains(name);
+            seen.push(*name);
+            new
+        });
+        // Stable, so variables that are not declared keep their order.
+        names.sort_by_key(|name| rank(name));
+        names
+            .into_iter()
+            .map(|name| Input {
+                name: name.to_string(),
+                spec: self.declared(name).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    fn names(plan: &Plan) -> Vec<&str> {
+        plan.inputs
+            .iter()
+            .map(|input| input.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn plan_covers_all_files_without_duplicates() {
+        let template = Template::builder()
+            .with_file(File::create("a.txt", "{{$A:read}} {{$PLAIN}} {{$A}}"))
+            .with_file(File::create(
+                "{{$PROJECTNAME}}/b.txt",
+                "{{$B:read}} {{$A:read}}",
+            ))
+            .with_file(File::create(
+                "c.txt",
+                "{{$B:sha256}} {{$C:sha256}} {{$ID:uuid}}",
+            ));
+
+        let plan = template.plan(&Context::new()).unwrap();
+        assert_eq!(names(&plan), ["PROJECTNAME", "A", "B", "C"]);
+        assert!(plan.missing.is_empty());
+
+        let plan = template
+            .plan(
+                &Context::new()
+                    .with_var("A", "a")
+                    .with_var("PROJECTNAME", "p")
+                    .strict(),
+            )
+            .unwrap();
+        assert_eq!(names(&plan), ["B", "C"]);
+        assert_eq!(plan.missing, ["PLAIN"]);
+    }
+
+    #[test]
+    fn declared_variables_are_asked_first_by_group() {
+        let template = Template::from_str(
+            r#"
+[variables]
+NAME = { group = "Service", prompt = "Service name", example = "billing" }
+LICENSE = "MIT"
+PORT = { group = "Network", help = "Port the service listens on" }
+OWNER = {}
+HOST = { group = "Network", default = "localhost" }
+TEAM = { group = "Service" }
+
+[[files]]
+path = "{{$PROJECTNAME}}/service.toml"
+content = "{{$EXTRA:read}} {{$OWNER}} {{$PORT}} {{$HOST}} {{$TEAM}} {{$NAME}} {{$LICENSE}}"
+"#,
+        )
+        .unwrap();
+
+        let plan = template.plan(&Context::new()).unwrap();
+        assert_eq!(
+            names(&plan),
+            ["NAME", "TEAM", "PORT", "OWNER", "PROJECTNAME", "EXTRA"]
+        );
+        assert_eq!(plan.inputs[0].question(), "Service name (e.g. billing)");
+        assert_eq!(plan.inputs[4].label(), "Project name");
+    }
+
+    #[test]
+    fn render_without_prompts_reports_every_input() {
+        let template = Template::builder()
+            .with_file(File::create("{{$PROJECTNAME}}/a.txt", "{{$A:read}}"))
+            .with_file(File::create("b.txt", "{{$B:read}}"));
+
+        let err = template
+            .render(&Context::new().non_interactive())
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::MissingVariables(ref names) if names == &["PROJECTNAME", "A", "B"])
+        );
+        assert!(Template::builder()
+            .with_file(File::create("b.txt", "{{$B:read}}"))
+            .plan(&Context::new().with_var("B", "b"))
+            .unwrap()
+            .is_complete());
+    }
+}
@@ -0,0 +1,83 @@
+//! JSON Schema for the template format (`spark schema`).
+//!
+//! The schema is generated from [`Template`] and the types it contains, so it
+//! always matches what Spark accepts. A copy is checked in at
+//! `schema/template.schema.json` for editors that load schemas by path or URL,
+//! e.g. VS Code or Neovim through taplo.
+
+use crate::Template;
+use schemars::gen::SchemaSettings;
+
+/// Where the checked-in copy of the schema lives, relative to the repository root.
+pub const SCHEMA_PATH: &str = "schema/template.schema.json";
+
+impl Template {
+    /// Returns the JSON Schema (draft 7) describing template TOML files.
+    ///
+    /// Unknown keys are rejected by the schema, so editors flag typos such as
+    /// `use_liqiud` that Spark itself would silently ignore.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::Template;
+    ///
+    /// let schema = Template::json_schema();
+    /// assert_eq!(schema["title"], "Template");
+    /// assert!(schema["properties"]["files"].is_object());
+    /// ```
+    pub fn json_schema() -> serde_json::Value {
+        let schema = SchemaSettings::draft07()
+            .into_generator()
+            .into_root_schema_for::<Template>();
+
+        serde_json::to_value(schema).expect("JSON Schema always serializes to JSON")
+    }
+
+    /// [`Template::json_schema`] as pretty-printed JSON, as printed by `spark schema`.
+    pub fn json_schema_string() -> String {
+        let mut schema = serde_json::to_string_pretty(&Self::json_schema())
+            .expect("JSON Schema always serializes to JSON");
+        schema.push('\n');
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_rejects_unknown_keys() {
+        let schema = Template::json_schema();
+        let definitions = &schema["definitions"];
+
+        assert_eq!(schema["additionalProperties"], false);
+        for name in ["Information", "Options", "File"] {
+            assert_eq!(definitions[name]["additionalProperties"], false, "{}", name);
+        }
+        assert!(definitions["Options"]["properties"]["use_liquid"].is_object());
+    }
+
+    #[test]
+    fn schema_does_not_expose_internal_fields() {
+        let schema = Template::json_schema();
+        let properties = schema["properties"].as_object().unwrap();
+
+        let mut keys: Vec<_> = properties.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["files", "info", "options", "variables"]);
+    }
+
+    #[test]
+    fn checked_in_schema_is_up_to_date() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_PATH);
+        let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+
+        assert!(
+            checked_in == Template::json_schema_string(),
+            "{} is stale, regenerate it with `cargo run -- schema > {}`",
+            SCHEMA_PATH,
+            SCHEMA_PATH
+        );
+    }
+}
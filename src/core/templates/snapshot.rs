@@ -0,0 +1,407 @@
+//! Golden-file snapshot tests for templates.
+//!
+//! Fixtures live next to the template file in a `<name>.tests/` directory, one
+//! sub-directory per case:
+//!
+//! ```text
+//! templates/
+//! ├── service.toml
+//! └── service.tests/
+//!     └── basic/
+//!         ├── from.txt     # optional, same `key=value` pairs as `--from`
+//!         ├── data.json    # optional, same as `--json`
+//!         └── expected/    # rendered output, one file per template file
+//! ```
+//!
+//! Cases render without prompting, with an empty environment, a fixed clock
+//! ([`SNAPSHOT_NOW`]) and seed ([`SNAPSHOT_SEED`]), so the output only changes
+//! when the template or its fixtures do.
+
+use crate::output_target::OutputTarget;
+use crate::utils::list_files;
+use crate::{vars, Context, Environment, Error, RenderedFile, Result, Template};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Suffix of the fixture directory placed next to a template file.
+pub const TESTS_DIR_SUFFIX: &str = "tests";
+/// Fixture file holding `key=value` pairs, separated by commas or newlines.
+pub const FROM_FILE: &str = "from.txt";
+/// Fixture file holding JSON data for `{{$.path}}` placeholders.
+pub const DATA_FILE: &str = "data.json";
+/// Directory holding the expected rendered output of a case.
+pub const EXPECTED_DIR: &str = "expected";
+/// Clock used for every snapshot render.
+pub const SNAPSHOT_NOW: &str = "2000-01-01T00:00:00Z";
+/// Seed used for every snapshot render.
+pub const SNAPSHOT_SEED: u64 = 0;
+
+/// A single fixture directory under `<name>.tests/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotCase {
+    /// Case name (the directory name).
+    pub name: String,
+    /// Path of the case directory.
+    pub dir: PathBuf,
+}
+
+/// Difference between the rendered output and the checked-in snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotDiff {
+    /// The snapshot contains a file the template no longer renders.
+    Missing(String),
+    /// The template renders a file that is not in the snapshot.
+    Unexpected(String),
+    /// The file content changed; `line` is the first differing line (1-based).
+    Changed {
+        /// Snapshot-relative path of the file.
+        path: String,
+        /// First line that differs.
+        line: usize,
+        /// Content stored in the snapshot.
+        expected: String,
+        /// Content rendered now.
+        actual: String,
+    },
+}
+
+/// Result of running one [`SnapshotCase`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotOutcome {
+    /// Case name.
+    pub case: String,
+    /// Differences found; empty when the case passes.
+    pub diffs: Vec<SnapshotDiff>,
+    /// Whether the snapshot was rewritten (`--bless`).
+    pub blessed: bool,
+}
+
+impl SnapshotOutcome {
+    /// Returns true if the rendered output matched the snapshot.
+    pub fn passed(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+impl SnapshotCase {
+    /// Lists the cases stored next to `template_path`, sorted by name.
+    ///
+    /// Returns an empty list if the template has no `<name>.tests/` directory.
+    pub fn discover(template_path: &Path) -> Result<Vec<Self>> {
+        let tests_dir = tests_dir(template_path);
+        if !tests_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut cases = Vec::new();
+        for entry in fs::read_dir(&tests_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                cases.push(Self {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    dir: entry.path(),
+                });
+            }
+        }
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(cases)
+    }
+
+    /// Directory holding the expected output of this case.
+    pub fn expected_dir(&self) -> PathBuf {
+        self.dir.join(EXPECTED_DIR)
+    }
+
+    /// Builds the deterministic, non-interactive context the case renders with.
+    pub fn context(&self) -> Result<Context> {
+        let now = chrono::DateTime::parse_from_rfc3339(SNAPSHOT_NOW)
+            .map_err(|e| Error::Custom(e.to_string()))?;
+        let mut context = Context::from_environment(&Environment::new())
+            .with_now(now)
+            .with_seed(SNAPSHOT_SEED)
+            .non_interactive();
+
+        let from_path = self.dir.join(FROM_FILE);
+        if from_path.is_file() {
+            let pairs =
+                vars::parse_from(&fs::read_to_string(&from_path)?).map_err(|e| Error::Data {
+                    path: from_path.display().to_string(),
+                    message: e.to_string(),
+                })?;
+            context = context.with_vars(pairs);
+        }
+
+        let data_path = self.dir.join(DATA_FILE);
+        if data_path.is_file() {
+            context = context.with_json_str(&fs::read_to_string(&data_path)?)?;
+        }
+
+        Ok(context)
+    }
+
+    /// Renders `template` and compares it with the snapshot. With `bless`, the
+    /// snapshot is replaced by the rendered output instead.
+    pub fn run(&self, template: &Template, bless: bool) -> Result<SnapshotOutcome> {
+        let rendered = template.render(&self.context()?)?;
+        let mut actual = Vec::with_capacity(rendered.len());
+        for file in rendered {
+            actual.push(RenderedFile::new(snapshot_path(&file.path)?, file.content));
+        }
+        actual.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let expected_dir = self.expected_dir();
+        if bless {
+            if expected_dir.exists() {
+                fs::remove_dir_all(&expected_dir)?;
+            }
+            for file in &actual {
+                let path = expected_dir.join(&file.path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, &file.content)?;
+            }
+
+            return Ok(SnapshotOutcome {
+                case: self.name.clone(),
+                diffs: Vec::new(),
+                blessed: true,
+            });
+        }
+
+        let expected = read_snapshot(&expected_dir)?;
+        let mut diffs = Vec::new();
+
+        for file in &expected {
+            match actual.iter().find(|f| f.path == file.path) {
+                None => diffs.push(SnapshotDiff::Missing(file.path.clone())),
+                Some(rendered) if rendered.content != file.content => {
+                    diffs.push(SnapshotDiff::Changed {
+                        path: file.path.clone(),
+                        line: first_difference(&file.content, &rendered.content),
+                        expected: file.content.clone(),
+                        actual: rendered.content.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        for file in &actual {
+            if !expected.iter().any(|f| f.path == file.path) {
+                diffs.push(SnapshotDiff::Unexpected(file.path.clone()));
+            }
+        }
+
+        Ok(SnapshotOutcome {
+            case: self.name.clone(),
+            diffs,
+            blessed: false,
+        })
+    }
+}
+
+impl Template {
+    /// Runs every snapshot case stored next to the template file (see
+    /// [`Template::with_source`] and the [`snapshot`](crate::templates::snapshot)
+    /// module for the fixture layout).
+    ///
+    /// # Errors
+    /// Fails if the template has no source path, a fixture is malformed, or a
+    /// case fails to render.
+    pub fn test_snapshots(&self, bless: bool) -> Result<Vec<SnapshotOutcome>> {
+        let source = self.source().ok_or_else(|| {
+            Error::Custom("Template has no source path to find snapshots next to".into())
+        })?;
+
+        SnapshotCase::discover(source)?
+            .iter()
+            .map(|case| case.run(self, bless))
+            .collect()
+    }
+}
+
+/// `templates/service.toml` → `templates/service.tests`.
+fn tests_dir(template_path: &Path) -> PathBuf {
+    template_path.with_extension(TESTS_DIR_SUFFIX)
+}
+
+/// Maps a rendered path to its location inside `expected/`. Non-file targets are
+/// stored as `@stdout`, `@stderr` and `@clipboard`.
+fn snapshot_path(path: &str) -> Result<String> {
+    match OutputTarget::from_path(path) {
+        OutputTarget::Stdout => Ok("@stdout".to_string()),
+        OutputTarget::Stderr => Ok("@stderr".to_string()),
+        OutputTarget::Clipboard => Ok("@clipboard".to_string()),
+        OutputTarget::File(file) => {
+            let mut parts = Vec::new();
+            for component in file.components() {
+                match component {
+                    Component::Normal(part) => parts.push(part.to_string_lossy()),
+                    Component::CurDir => {}
+                    _ => return Err(Error::InvalidPath(file)),
+                }
+            }
+            if parts.is_empty() || path.starts_with('~') {
+                return Err(Error::InvalidPath(file));
+            }
+            Ok(parts.join("/"))
+        }
+    }
+}
+
+fn read_snapshot(dir: &Path) -> Result<Vec<RenderedFile>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for file in list_files(dir)? {
+        let relative = Path::new(&file)
+            .strip_prefix(dir)
+            .map_err(|_| Error::InvalidPath(PathBuf::from(&file)))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        files.push(RenderedFile::new(relative, fs::read_to_string(&file)?));
+    }
+
+    Ok(files)
+}
+
+fn first_difference(expected: &str, actual: &str) -> usize {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            _ => return line,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(name: &str, template: &str) -> (PathBuf, Template) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("demo.tests/basic")).unwrap();
+
+        let template_path = dir.join("demo.toml");
+        fs::write(&template_path, template).unwrap();
+        fs::write(
+            dir.join("demo.tests/basic/from.txt"),
+            "NAME=spark\nLANG=rust",
+        )
+        .unwrap();
+
+        (dir, Template::from_file(&template_path).unwrap())
+    }
+
+    const TEMPLATE: &str = r##"
+[[files]]
+path = "{{$NAME}}/README.md"
+content = "# {{$NAME}} in {{$LANG}}, {{$YYYY}}"
+
+[[files]]
+path = "stdout://"
+content = "done {{$ID:uuid}}"
+"##;
+
+    #[test]
+    fn bless_writes_snapshot_and_run_passes() {
+        let (dir, template) = setup("spark_test_snapshot_bless", TEMPLATE);
+
+        let blessed = template.test_snapshots(true).unwrap();
+        assert_eq!(blessed.len(), 1);
+        assert!(blessed[0].blessed);
+
+        let expected = dir.join("demo.tests/basic/expected");
+        assert_eq!(
+            fs::read_to_string(expected.join("spark/README.md")).unwrap(),
+            "# spark in rust, 2000"
+        );
+        assert!(expected.join("@stdout").is_file());
+
+        let outcomes = template.test_snapshots(false).unwrap();
+        assert!(outcomes[0].passed(), "{:?}", outcomes[0].diffs);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn bless_strips_leading_current_dir() {
+        let (dir, template) = setup(
+            "spark_test_snapshot_curdir",
+            "[[files]]\npath = \"./{{$NAME}}.txt\"\ncontent = \"{{$LANG}}\"\n",
+        );
+
+        template.test_snapshots(true).unwrap();
+        assert!(dir.join("demo.tests/basic/expected/spark.txt").is_file());
+
+        let outcomes = template.test_snapshots(false).unwrap();
+        assert!(outcomes[0].passed(), "{:?}", outcomes[0].diffs);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn run_reports_changed_missing_and_unexpected_files() {
+        let (dir, template) = setup("spark_test_snapshot_diff", TEMPLATE);
+        template.test_snapshots(true).unwrap();
+
+        let expected = dir.join("demo.tests/basic/expected");
+        fs::write(expected.join("spark/README.md"), "# old").unwrap();
+        fs::write(expected.join("stale.txt"), "gone").unwrap();
+        fs::remove_file(expected.join("@stdout")).unwrap();
+
+        let outcome = &template.test_snapshots(false).unwrap()[0];
+        assert!(!outcome.passed());
+        assert!(outcome
+            .diffs
+            .contains(&SnapshotDiff::Missing("stale.txt".into())));
+        assert!(outcome
+            .diffs
+            .contains(&SnapshotDiff::Unexpected("@stdout".into())));
+        assert!(outcome.diffs.iter().any(|d| matches!(
+            d,
+            SnapshotDiff::Changed { path, line: 1, .. } if path == "spark/README.md"
+        )));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn template_without_tests_dir_has_no_cases() {
+        let dir = std::env::temp_dir().join("spark_test_snapshot_none");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("demo.toml"), TEMPLATE).unwrap();
+
+        let template = Template::from_file(dir.join("demo.toml")).unwrap();
+        assert!(template.test_snapshots(false).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn snapshot_path_rejects_escaping_paths() {
+        assert_eq!(snapshot_path("a/b.txt").unwrap(), "a/b.txt");
+        assert_eq!(snapshot_path("./a.txt").unwrap(), "a.txt");
+        assert_eq!(snapshot_path("a/./b.txt").unwrap(), "a/b.txt");
+        assert!(snapshot_path("./").is_err());
+        assert!(snapshot_path("./../x").is_err());
+        assert_eq!(snapshot_path("clipboard://").unwrap(), "@clipboard");
+        assert!(snapshot_path("/etc/passwd").is_err());
+        assert!(snapshot_path("../x").is_err());
+        assert!(snapshot_path("~/x").is_err());
+    }
+}
@@ -0,0 +1,429 @@
+//! Detection of unknown keys in template TOML.
+//!
+//! Templates deserialize leniently, so a misspelled key such as `use_liqiud` is
+//! dropped without a word. [`Template::unknown_keys`] lists such keys together
+//! with their position and the closest known key, [`Template::from_str_strict`]
+//! turns them into [`Error::UnknownKeys`], and [`Template::lint`] reports them as
+//! `unknown-key` diagnostics. Free-form metadata belongs in `[info.extra]`.
+
+use crate::{Error, Result, Template};
+use std::fmt;
+use std::path::Path;
+
+/// Minimum Jaro-Winkler similarity for a known key to be suggested.
+const SUGGESTION_THRESHOLD: f64 = 0.8;
+
+/// A key in the template TOML that Spark does not understand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// Dotted path of the key, e.g. `options.use_liqiud` or `files[1].contnet`.
+    pub path: String,
+    /// Closest known key at the same level, if any is similar enough.
+    pub suggestion: Option<String>,
+    /// 1-based line of the key in the TOML source, if found.
+    pub line: Option<usize>,
+    /// 1-based column of the key in the TOML source, if found.
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+        write!(f, "unknown key '{}'", self.path)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean '{}'?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl Template {
+    /// Parses a template like [`Template::from_str`], but fails with
+    /// [`Error::UnknownKeys`] if the TOML contains keys Spark does not understand.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use spark::{Error, Template};
+    ///
+    /// let toml = r#"
+    /// [options]
+    /// use_liqiud = false
+    /// "#;
+    ///
+    /// assert!(Template::from_str(toml).is_ok());
+    /// match Template::from_str_strict(toml) {
+    ///     Err(Error::UnknownKeys(keys)) => {
+    ///         assert_eq!(keys[0].path, "options.use_liqiud");
+    ///         assert_eq!(keys[0].suggestion.as_deref(), Some("use_liquid"));
+    ///     }
+    ///     other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    /// }
+    /// ```
+    pub fn from_str_strict(toml_str: &str) -> Result<Self> {
+        let unknown = Self::unknown_keys(toml_str)?;
+        if !unknown.is_empty() {
+            return Err(Error::UnknownKeys(unknown));
+        }
+        Self::from_str(toml_str)
+    }
+
+    /// Reads a template like [`Template::from_file`], rejecting unknown keys like
+    /// [`Template::from_str_strict`].
+    pub fn from_file_strict(path: impl AsRef<Path>) -> Result<Self> {
+        let template = Self::from_file(path)?;
+        let unknown = Self::unknown_keys(template.raw.as_deref().unwrap_or_default())?;
+        if !unknown.is_empty() {
+            return Err(Error::UnknownKeys(unknown));
+        }
+        Ok(template)
+    }
+
+    /// Lists the keys in `toml_str` that [`Template::from_str`] would ignore.
+    pub fn unknown_keys(toml_str: &str) -> Result<Vec<UnknownKey>> {
+        let mut ignored = Vec::new();
+        let mut deserializer = toml::Deserializer::new(toml_str);
+        serde_ignored::deserialize(&mut deserializer, |path| {
+            ignored.push(segments(&path));
+        })
+        .map(|_: Template| ())?;
+        deserializer.end()?;
+
+        Ok(ignored
+            .into_iter()
+            .map(|segments| {
+                let position = locate(toml_str, &segments);
+                UnknownKey {
+                    path: display(&segments),
+                    suggestion: suggest(&segments),
+                    line: position.map(|(line, _)| line),
+                    column: position.map(|(_, column)| column),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Keys understood at the level of `parent`. Kept in sync with the structs by
+/// `known_keys_match_schema`.
+fn known_keys(parent: &[Segment]) -> &'static [&'static str] {
+    match parent {
+        [] => &["info", "options", "variables", "files"],
+        [Segment::Key(key)] if key == "info" => &["name", "author", "description", "extra"],
+        [Segment::Key(key)] if key == "options" => {
+            &["git", "use_liquid", "json_data", "project_root"]
+        }
+        [Segment::Key(key), Segment::Index(_)] if key == "files" => &["path", "content"],
+        [Segment::Key(key), Segment::Key(_)] if key == "variables" => {
+            &["default", "prompt", "help", "example", "group"]
+        }
+        _ => &[],
+    }
+}
+
+fn suggest(segments: &[Segment]) -> Option<String> {
+    let (Segment::Key(key), parent) = segments.split_last()? else {
+        return None;
+    };
+
+    known_keys(parent)
+        .iter()
+        .map(|known| (strsim::jaro_winkler(key, known), known))
+        .filter(|(score, _)| *score >= SUGGESTION_THRESHOLD)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, known)| known.to_string())
+}
+
+fn segments(path: &serde_ignored::Path) -> Vec<Segment> {
+    match path {
+        serde_ignored::Path::Root => Vec::new(),
+        serde_ignored::Path::Seq { parent, index } => {
+            let mut segments = segments(parent);
+            segments.push(Segment::Index(*index));
+            segments
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            let mut segments = segments(parent);
+            segments.push(Segment::Key(key.clone()));
+            segments
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => segments(parent),
+    }
+}
+
+fn display(segments: &[Segment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Key(key) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+            }
+            Segment::Index(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+/// Finds the line and column of the key at `segments` by tracking table headers.
+/// Covers the `key = value`, `[table]` and `[[array]]` forms templates use.
+fn locate(raw: &str, segments: &[Segment]) -> Option<(usize, usize)> {
+    let (Segment::Key(key), parent) = segments.split_last()? else {
+        return None;
+    };
+    let parent_header: Vec<String> = parent
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Key(key) => Some(key.clone()),
+            Segment::Index(_) => None,
+        })
+        .collect();
+    let wanted_index = parent.iter().rev().find_map(|segment| match segment {
+        Segment::Index(index) => Some(*index),
+        Segment::Key(_) => None,
+    });
+
+    let mut own_header = parent_header.clone();
+    own_header.push(key.clone());
+
+    let mut current: Vec<String> = Vec::new();
+    let mut array_counts: Vec<(Vec<String>, usize)> = Vec::new();
+    let mut current_index = None;
+    let mut open_string: Option<&str> = None;
+
+    for (number, line) in raw.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let column = line.len() - trimmed.len() + 1;
+
+        // Skip the inside of multi-line strings, file contents often contain `[x]` lines.
+        let was_open = open_string.is_some();
+        for delimiter in ["\"\"\"", "'''"] {
+            if open_string.is_none_or(|open| open == delimiter)
+                && line.matches(delimiter).count() % 2 == 1
+            {
+                open_string = match open_string {
+                    Some(_) => None,
+                    None => Some(delimiter),
+                };
+            }
+        }
+        if was_open {
+            continue;
+        }
+
+        if let Some(header) = parse_header(trimmed) {
+            let (name, is_array) = header;
+            if is_array {
+                let count = match array_counts.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count
+                    }
+                    None => {
+                        array_counts.push((name.clone(), 0));
+                        0
+                    }
+                };
+                current_index = Some(count);
+            } else {
+                current_index = None;
+            }
+
+            if name == own_header && (wanted_index.is_none() || current_index == wanted_index) {
+                return Some((number + 1, column));
+            }
+            current = name;
+            continue;
+        }
+
+        let in_parent =
+            current == parent_header && (wanted_index.is_none() || current_index == wanted_index);
+        if in_parent {
+            if let Some((name, _)) = trimmed.split_once('=') {
+                if name.trim().trim_matches(['"', '\'']) == key {
+                    return Some((number + 1, column));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Parses `[a.b]` / `[[a.b]]` into its dotted path and whether it is an array of tables.
+fn parse_header(line: &str) -> Option<(Vec<String>, bool)> {
+    let line = line.split('#').next()?.trim_end();
+    let (inner, is_array) = if let Some(inner) = line.strip_prefix("[[") {
+        (inner.strip_suffix("]]")?, true)
+    } else {
+        (line.strip_prefix('[')?.strip_suffix(']')?, false)
+    };
+
+    Some((
+        inner
+            .split('.')
+            .map(|part| part.trim().trim_matches(['"', '\'']).to_string())
+            .collect(),
+        is_array,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_misspelled_keys_with_positions_and_suggestions() {
+        let toml = r#"[info]
+name = "demo"
+autor = "me"
+
+[options]
+use_liqiud = false
+
+[[files]]
+path = "a.txt"
+content = ""
+
+[[files]]
+path = "b.txt"
+contnet = "typo"
+content = '''
+[info]
+autor = "not a key"
+'''
+"#;
+
+        let keys = Template::unknown_keys(toml).unwrap();
+        assert_eq!(
+            keys,
+            vec![
+                UnknownKey {
+                    path: "info.autor".into(),
+                    suggestion: Some("author".into()),
+                    line: Some(3),
+                    column: Some(1),
+                },
+                UnknownKey {
+                    path: "options.use_liqiud".into(),
+                    suggestion: Some("use_liquid".into()),
+                    line: Some(6),
+                    column: Some(1),
+                },
+                UnknownKey {
+                    path: "files[1].contnet".into(),
+                    suggestion: Some("content".into()),
+                    line: Some(14),
+                    column: Some(1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_tables_are_located_by_header() {
+        let keys = Template::unknown_keys("[opitons]\ngit = true\n").unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].path, "opitons");
+        assert_eq!(keys[0].suggestion.as_deref(), Some("options"));
+        assert_eq!((keys[0].line, keys[0].column), (Some(1), Some(1)));
+    }
+
+    #[test]
+    fn info_extra_is_free_form() {
+        let toml = r#"
+[info]
+name = "demo"
+
+[info.extra]
+reference = "https://example.com"
+tags = ["web"]
+"#;
+
+        assert!(Template::unknown_keys(toml).unwrap().is_empty());
+        let template = Template::from_str_strict(toml).unwrap();
+        assert_eq!(
+            template.info.unwrap().extra["reference"],
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn variable_tables_are_checked() {
+        let toml =
+            "[variables]\nLICENSE = \"MIT\"\nPORT = { default = \"80\", promt = \"Port\" }\n";
+        let keys = Template::unknown_keys(toml).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].path, "variables.PORT.promt");
+        assert_eq!(keys[0].suggestion.as_deref(), Some("prompt"));
+    }
+
+    #[test]
+    fn unrelated_keys_get_no_suggestion() {
+        let keys = Template::unknown_keys("zzz = 1\n").unwrap();
+        assert_eq!(keys[0].suggestion, None);
+    }
+
+    #[test]
+    fn from_str_strict_rejects_and_from_str_accepts() {
+        let toml = "[options]\nuse_liqiud = false\n";
+        assert!(Template::from_str(toml).is_ok());
+        assert!(matches!(
+            Template::from_str_strict(toml),
+            Err(Error::UnknownKeys(keys)) if keys.len() == 1
+        ));
+    }
+
+    #[test]
+    fn known_keys_match_schema() {
+        let schema = Template::json_schema();
+        let properties = |value: &serde_json::Value| {
+            let mut keys: Vec<String> = value["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect();
+            keys.sort();
+            keys
+        };
+        let sorted = |keys: &[&str]| {
+            let mut keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+            keys.sort();
+            keys
+        };
+
+        let files = [Segment::Key("files".into()), Segment::Index(0)];
+        assert_eq!(properties(&schema), sorted(known_keys(&[])));
+        assert_eq!(
+            properties(&schema["definitions"]["Information"]),
+            sorted(known_keys(&[Segment::Key("info".into())]))
+        );
+        assert_eq!(
+            properties(&schema["definitions"]["Options"]),
+            sorted(known_keys(&[Segment::Key("options".into())]))
+        );
+        assert_eq!(
+            properties(&schema["definitions"]["File"]),
+            sorted(known_keys(&files))
+        );
+        assert_eq!(
+            properties(&schema["definitions"]["VariableSpec"]),
+            sorted(known_keys(&[
+                Segment::Key("variables".into()),
+                Segment::Key("PORT".into())
+            ]))
+        );
+    }
+}
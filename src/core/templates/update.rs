@@ -0,0 +1,428 @@
+//! Updating generated projects to a newer version of their template (`spark update`).
+//!
+//! A project generated with a [manifest](super::manifest) carries the template
+//! it came from (`.spark/template.toml`) and the variables it was rendered with.
+//! Updating re-renders both the saved template and the current one with those
+//! variables and merges each file three ways:
+//!
+//! - the **base** is the original render,
+//! - **ours** is the file as it is in the project now,
+//! - **theirs** is the render of the current template.
+//!
+//! Files the user did not touch are replaced, local edits are kept, and edits to
+//! the same lines on both sides leave conflict markers in the file.
+
+use super::manifest::Manifest;
+use crate::output_target::OutputTarget;
+use crate::transaction::Transaction;
+use crate::{Context, Error, RenderedFile, Result, Template};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::Path,
+};
+
+/// What [`Template::update`] did to a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateStatus {
+    /// The template gained the file and it was created.
+    Added,
+    /// The file was not modified locally and now holds the new render.
+    Updated,
+    /// Local edits and template changes were merged without conflicts.
+    Merged,
+    /// Local edits and template changes overlap; the file has conflict markers.
+    Conflict,
+    /// The template dropped the file and it was not modified locally, so it was deleted.
+    Removed,
+    /// The template dropped the file but it was modified locally, so it was kept.
+    Kept,
+    /// The template changed the file but it was deleted locally, so it was not recreated.
+    Skipped,
+    /// The template changed the file but it is not UTF-8 text locally, so it was
+    /// left alone to be updated by hand.
+    Binary,
+}
+
+/// A file touched by [`Template::update`], relative to the project directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileUpdate {
+    /// Path relative to the project directory.
+    pub path: String,
+    /// What happened to the file.
+    pub status: UpdateStatus,
+}
+
+/// Result of [`Template::update`]. Files the update left alone are not listed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpdateReport {
+    /// Files that were changed, or that need attention, sorted by path.
+    pub files: Vec<FileUpdate>,
+}
+
+impl UpdateReport {
+    /// Whether any file was left with conflict markers.
+    pub fn has_conflicts(&self) -> bool {
+        self.files
+            .iter()
+            .any(|file| file.status == UpdateStatus::Conflict)
+    }
+}
+
+impl fmt::Display for UpdateStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Added => "added",
+            Self::Updated => "updated",
+            Self::Merged => "merged",
+            Self::Conflict => "conflict",
+            Self::Removed => "removed",
+            Self::Kept => "kept",
+            Self::Skipped => "skipped",
+            Self::Binary => "binary",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Change to apply to a single file.
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    Write(String),
+    Delete,
+    Keep,
+}
+
+impl Template {
+    /// Updates the project in `project_dir`, generated from an earlier version of
+    /// this template with a manifest, to this version.
+    ///
+    /// The saved variables take precedence over the ones in `context`; variables
+    /// the new version introduces come from `context` or are prompted for. The
+    /// manifest and the saved template are refreshed afterwards, so the next
+    /// update merges against this version. Either every change is applied or,
+    /// if a write fails, none is.
+    ///
+    /// Unless the context is [trusted](Context::trusted), rendered paths must
+    /// stay inside `project_dir`.
+    ///
+    /// Files that are not UTF-8 text in the project are not merged; they are
+    /// reported as [`UpdateStatus::Binary`] when the template changed them.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidPath`] if the project has no manifest or saved
+    /// template, and any error rendering either version of the template. Secret
+    /// variables (see [`is_secret`](super::manifest::is_secret)) are not saved in
+    /// the manifest, so a non-interactive update fails with
+    /// [`Error::MissingVariable`] unless `context` provides them.
+    pub fn update(&self, project_dir: &Path, context: &Context) -> Result<UpdateReport> {
+        let manifest = Manifest::read(project_dir)?;
+        let mut original = Manifest::read_template(project_dir)?;
+        original.source = self.source.clone();
+
+        let mut context = context.clone().with_vars(manifest.variables.clone());
+        let (base, keywords, base_options) = original.render_inner(&context)?;
+        context.keywords.extend(keywords);
+        let (new, keywords, options) = self.render_inner(&context)?;
+
+        let base = project_files(base, &base_options.project_root);
+        let new = project_files(new, &options.project_root);
+
+        if !context.trusted {
+            for path in new.keys() {
+                OutputTarget::from_path(path).ensure_within(project_dir)?;
+            }
+        }
+
+        let mut updated = Manifest::new(self, &keywords);
+        for (path, content) in &new {
+            updated.record_file(path.clone(), content.as_bytes());
+        }
+
+        let mut transaction = Transaction::new(context.events.clone());
+        let result = apply(&base, &new, project_dir, &mut transaction).and_then(|report| {
+            if updated != manifest {
+                updated.save(self, project_dir, &mut transaction)?;
+            }
+            Ok(report)
+        });
+        if result.is_err() {
+            transaction.rollback();
+        }
+        result
+    }
+}
+
+/// Filesystem outputs of a render keyed by their path relative to the project.
+fn project_files(rendered: Vec<RenderedFile>, project_root: &str) -> BTreeMap<String, String> {
+    rendered
+        .into_iter()
+        .filter(|file| {
+            OutputTarget::from_path(&file.path)
+                .expanded_path()
+                .is_some()
+        })
+        .filter_map(|file| Some((Manifest::file_key(&file.path, project_root)?, file.content)))
+        .collect()
+}
+
+fn apply(
+    base: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    project_dir: &Path,
+    transaction: &mut Transaction,
+) -> Result<UpdateReport> {
+    let paths: BTreeSet<&String> = base.keys().chain(new.keys()).collect();
+    let mut report = UpdateReport::default();
+
+    for path in paths {
+        let target = project_dir.join(path);
+        let current = match fs::read(&target).map(String::from_utf8) {
+            Ok(Ok(content)) => Some(content),
+            Ok(Err(_)) => {
+                if base.get(path) != new.get(path) {
+                    report.files.push(FileUpdate {
+                        path: path.clone(),
+                        status: UpdateStatus::Binary,
+                    });
+                }
+                continue;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let Some((status, action)) = resolve(
+            base.get(path).map(String::as_str),
+            current.as_deref(),
+            new.get(path).map(String::as_str),
+        ) else {
+            continue;
+        };
+
+        match action {
+            Action::Write(content) => transaction
+                .write(path, &OutputTarget::File(target), &content)
+                .map_err(|e| Error::OutputWrite {
+                    path: path.clone(),
+                    message: e.to_string(),
+                })?,
+            Action::Delete => transaction.remove(&target)?,
+            Action::Keep => {}
+        }
+        report.files.push(FileUpdate {
+            path: path.clone(),
+            status,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Three-way decision for one file; `None` when there is nothing to do or report.
+fn resolve(
+    base: Option<&str>,
+    current: Option<&str>,
+    new: Option<&str>,
+) -> Option<(UpdateStatus, Action)> {
+    if base == new || current == new {
+        return None;
+    }
+
+    match (base, current, new) {
+        (_, None, Some(new)) if base.is_none() => {
+            Some((UpdateStatus::Added, Action::Write(new.to_string())))
+        }
+        (_, None, _) => Some((UpdateStatus::Skipped, Action::Keep)),
+        (Some(base), Some(current), None) if base == current => {
+            Some((UpdateStatus::Removed, Action::Delete))
+        }
+        (_, Some(_), None) => Some((UpdateStatus::Kept, Action::Keep)),
+        (Some(base), Some(current), Some(new)) if base == current => {
+            Some((UpdateStatus::Updated, Action::Write(new.to_string())))
+        }
+        (base, Some(current), Some(new)) => match diffy::merge(base.unwrap_or(""), current, new) {
+            Ok(merged) => Some((UpdateStatus::Merged, Action::Write(merged))),
+            Err(conflicted) => Some((UpdateStatus::Conflict, Action::Write(conflicted))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    #[test]
+    fn resolve_covers_each_case() {
+        let write = |s: &str| Action::Write(s.to_string());
+
+        assert_eq!(resolve(Some("a"), Some("x"), Some("a")), None);
+        assert_eq!(
+            resolve(None, None, Some("n")),
+            Some((UpdateStatus::Added, write("n")))
+        );
+        assert_eq!(
+            resolve(Some("a"), Some("a"), Some("b")),
+            Some((UpdateStatus::Updated, write("b")))
+        );
+        assert_eq!(
+            resolve(Some("a"), None, Some("b")),
+            Some((UpdateStatus::Skipped, Action::Keep))
+        );
+        assert_eq!(
+            resolve(Some("a"), Some("a"), None),
+            Some((UpdateStatus::Removed, Action::Delete))
+        );
+        assert_eq!(
+            resolve(Some("a"), Some("x"), None),
+            Some((UpdateStatus::Kept, Action::Keep))
+        );
+        assert_eq!(
+            resolve(Some("1\n2\n3\n"), Some("0\n2\n3\n"), Some("1\n2\n4\n")),
+            Some((UpdateStatus::Merged, write("0\n2\n4\n")))
+        );
+        assert!(matches!(
+            resolve(Some("1\n"), Some("2\n"), Some("3\n")),
+            Some((UpdateStatus::Conflict, Action::Write(ref merged))) if merged.contains("<<<<<<<")
+        ));
+    }
+
+    #[test]
+    fn update_merges_template_changes_into_generated_project() {
+        let root = std::env::temp_dir().join(format!("spark-update-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let v1 = Template::from_str(
+            r#"
+[[files]]
+path = "ci.yml"
+content = "name: {{$NAME}}\nrun: test\n"
+
+[[files]]
+path = "notes.md"
+content = "notes\n"
+
+[[files]]
+path = "old.txt"
+content = "old\n"
+"#,
+        )
+        .unwrap();
+        let ctx = Context::new()
+            .with_var("NAME", "billing")
+            .with_output_root(&root)
+            .with_manifest(true)
+            .non_interactive();
+        v1.extract_with_context(&ctx).unwrap();
+        fs::write(root.join("notes.md"), "my notes\n").unwrap();
+
+        let v2 = Template::from_str(
+            r#"
+[[files]]
+path = "ci.yml"
+content = "name: {{$NAME}}\nrun: test --all\n"
+
+[[files]]
+path = "notes.md"
+content = "template notes\n"
+
+[[files]]
+path = "lint.yml"
+content = "lint: {{$NAME}}\n"
+"#,
+        )
+        .unwrap();
+        let report = v2.update(&root, &Context::new().non_interactive()).unwrap();
+
+        let statuses: Vec<_> = report
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("ci.yml", UpdateStatus::Updated),
+                ("lint.yml", UpdateStatus::Added),
+                ("notes.md", UpdateStatus::Conflict),
+                ("old.txt", UpdateStatus::Removed),
+            ]
+        );
+        assert!(report.has_conflicts());
+        assert_eq!(
+            fs::read_to_string(root.join("ci.yml")).unwrap(),
+            "name: billing\nrun: test --all\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("lint.yml")).unwrap(),
+            "lint: billing\n"
+        );
+        assert!(fs::read_to_string(root.join("notes.md"))
+            .unwrap()
+            .contains("<<<<<<<"));
+        assert!(!root.join("old.txt").exists());
+
+        let manifest = Manifest::read(&root).unwrap();
+        assert_eq!(
+            manifest.template.hash,
+            super::super::manifest::digest(v2.content().as_bytes())
+        );
+        assert_eq!(
+            Manifest::read_template(&root).unwrap().content(),
+            v2.content()
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn update_reports_files_that_are_not_text() {
+        let root = std::env::temp_dir().join(format!("spark-update-bin-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let v1 = Template::builder()
+            .with_file(File::create("logo.svg", "v1"))
+            .with_file(File::create("same.txt", "same"))
+            .with_file(File::create("README.md", "v1"));
+        let ctx = Context::new()
+            .with_output_root(&root)
+            .with_manifest(true)
+            .non_interactive();
+        v1.extract_with_context(&ctx).unwrap();
+        fs::write(root.join("logo.svg"), [0xff, 0xfe, 0x00]).unwrap();
+        fs::write(root.join("same.txt"), [0xff]).unwrap();
+
+        let v2 = Template::builder()
+            .with_file(File::create("logo.svg", "v2"))
+            .with_file(File::create("same.txt", "same"))
+            .with_file(File::create("README.md", "v2"));
+        let report = v2.update(&root, &Context::new().non_interactive()).unwrap();
+
+        let statuses: Vec<_> = report
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("README.md", UpdateStatus::Updated),
+                ("logo.svg", UpdateStatus::Binary),
+            ]
+        );
+        assert_eq!(fs::read(root.join("logo.svg")).unwrap(), [0xff, 0xfe, 0x00]);
+        assert_eq!(fs::read_to_string(root.join("README.md")).unwrap(), "v2");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn update_requires_a_manifest() {
+        let template = Template::builder().with_file(File::create("a", "b"));
+        let err = template
+            .update(Path::new("/nonexistent/project"), &Context::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidPath(_)));
+    }
+}
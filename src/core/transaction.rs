@@ -0,0 +1,185 @@
+//! Filesystem changes made by a single extraction or update, so they can be undone.
+//!
+//! [`Transaction::write`] records every file and directory it creates and keeps
+//! the previous contents of files it overwrites or [removes](Transaction::remove).
+//! If a later write or a post-generation step fails, [`Transaction::rollback`]
+//! restores the output directory to the state it was in before the operation.
+//! Directories other steps create, such as the `.git` of `git init`, can be
+//! recorded with [`Transaction::record_dir`] to be removed as well.
+//! Every write is reported as an [`Event::FileWritten`].
+
+use crate::events::{Event, Events};
+use crate::output_target::OutputTarget;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    /// Directories that did not exist before, outermost first, followed by the
+    /// ones recorded with `record_dir`.
+    created_dirs: Vec<PathBuf>,
+    created_files: Vec<PathBuf>,
+    /// Files that were overwritten or removed, with their previous contents.
+    replaced_files: Vec<(PathBuf, Vec<u8>)>,
+    events: Events,
+}
+
+impl Transaction {
+    pub(crate) fn new(events: Events) -> Self {
+        Self {
+            events,
+            ..Self::default()
+        }
+    }
+
+    /// Writes `content` to `target`, recording what has to be undone on rollback.
+    /// Stream targets cannot be undone and are written as-is. `path` is the
+    /// rendered path reported in the event.
+    pub(crate) fn write(
+        &mut self,
+        path: &str,
+        target: &OutputTarget,
+        content: &str,
+    ) -> io::Result<()> {
+        self.write_target(target, content)?;
+        self.events.emit(Event::file_written(path, target, content));
+        Ok(())
+    }
+
+    fn write_target(&mut self, target: &OutputTarget, content: &str) -> io::Result<()> {
+        let Some(path) = target.expanded_path() else {
+            return target.write(content);
+        };
+
+        if let Some(parent) = path.parent() {
+            self.create_dirs(parent)?;
+        }
+
+        match fs::read(&path) {
+            Ok(previous) => self.replaced_files.push((path, previous)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => self.created_files.push(path),
+            Err(e) => return Err(e),
+        }
+
+        target.write(content)
+    }
+
+    /// Deletes the file at `path`, keeping its contents for rollback.
+    pub(crate) fn remove(&mut self, path: &Path) -> io::Result<()> {
+        let previous = fs::read(path)?;
+        fs::remove_file(path)?;
+        self.replaced_files.push((path.to_path_buf(), previous));
+        Ok(())
+    }
+
+    fn create_dirs(&mut self, dir: &Path) -> io::Result<()> {
+        let missing: Vec<_> = dir
+            .ancestors()
+            .take_while(|d| !d.as_os_str().is_empty() && !d.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(dir)?;
+        self.created_dirs.extend(missing.into_iter().rev());
+        Ok(())
+    }
+
+    /// Records `dir`, about to be created by a step outside the transaction, so
+    /// that rollback removes it. Does nothing if it already exists.
+    pub(crate) fn record_dir(&mut self, dir: PathBuf) {
+        if !dir.exists() {
+            self.created_dirs.push(dir);
+        }
+    }
+
+    /// Restores overwritten files and removes everything this transaction created,
+    /// including files other steps (e.g. `git init`) put into created directories.
+    pub(crate) fn rollback(self) {
+        for (path, previous) in self.replaced_files.into_iter().rev() {
+            let _ = fs::write(path, previous);
+        }
+        for path in self.created_files.into_iter().rev() {
+            let _ = fs::remove_file(path);
+        }
+        for dir in &self.created_dirs {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("spark-tx-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rollback_removes_created_files_and_directories() {
+        let root = scratch("created");
+        let mut tx = Transaction::new(Events::default());
+
+        tx.write(
+            "a/b/c.txt",
+            &OutputTarget::File(root.join("a/b/c.txt")),
+            "new",
+        )
+        .unwrap();
+        tx.write("top.txt", &OutputTarget::File(root.join("top.txt")), "new")
+            .unwrap();
+        assert!(root.join("a/b/c.txt").exists());
+
+        tx.rollback();
+        assert!(!root.join("a").exists());
+        assert!(!root.join("top.txt").exists());
+        assert!(root.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rollback_restores_overwritten_files() {
+        let root = scratch("replaced");
+        let existing = root.join("keep.txt");
+        fs::write(&existing, "original").unwrap();
+
+        let mut tx = Transaction::new(Events::default());
+        tx.write("keep.txt", &OutputTarget::File(existing.clone()), "changed")
+            .unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "changed");
+
+        tx.rollback();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "original");
+
+        let mut tx = Transaction::new(Events::default());
+        tx.remove(&existing).unwrap();
+        assert!(!existing.exists());
+        tx.rollback();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "original");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rollback_removes_recorded_dirs_in_existing_directories() {
+        let root = scratch("recorded");
+        fs::create_dir_all(root.join("old/.git")).unwrap();
+
+        let mut tx = Transaction::new(Events::default());
+        tx.record_dir(root.join(".git"));
+        tx.record_dir(root.join("old/.git"));
+        fs::create_dir_all(root.join(".git/objects")).unwrap();
+
+        tx.rollback();
+        assert!(!root.join(".git").exists());
+        assert!(root.join("old/.git").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
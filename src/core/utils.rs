@@ -1,23 +1,11 @@
-use colored::*;
 use std::{fs, io, path::Path};
 use walkdir::WalkDir;
 
 #[allow(dead_code)]
-pub fn create_dirs(dir: &str) {
-    match shellexpand::full(dir) {
-        Ok(expanded) => {
-            if let Err(e) = fs::create_dir_all(expanded.as_ref()) {
-                eprintln!("{}: {}", "error".red(), e);
-            } else {
-                println!(
-                    "{}: {}",
-                    "creating directory".blue(),
-                    expanded.to_string().bold().green()
-                );
-            }
-        }
-        Err(e) => eprintln!("{}: Failed to expand path '{}': {}", "error".red(), dir, e),
-    }
+pub fn create_dirs(dir: &str) -> io::Result<()> {
+    let expanded =
+        shellexpand::full(dir).map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))?;
+    fs::create_dir_all(expanded.as_ref())
 }
 
 pub fn write_content<P: AsRef<Path>>(path: P, content: &str) -> std::io::Result<()> {
@@ -32,13 +20,6 @@ pub fn write_content<P: AsRef<Path>>(path: P, content: &str) -> std::io::Result<
         Path::new(&expanded_path),
         content.replace("initPJNAME", "{{$PROJECTNAME}}"),
     )
-    .map(|_| {
-        println!(
-            "{}: {}",
-            "file written".blue(),
-            expanded_path.bold().green()
-        );
-    })
 }
 
 pub fn list_files(dir: &Path) -> Result<Vec<String>, io::Error> {
@@ -72,7 +53,7 @@ mod tests {
         let dir_path = std::env::temp_dir().join("spark_test_create_dirs/nested");
         let _ = fs::remove_dir_all(dir_path.parent().unwrap());
 
-        create_dirs(&dir_path.to_string_lossy());
+        create_dirs(&dir_path.to_string_lossy()).unwrap();
         assert!(dir_path.is_dir());
 
         let _ = fs::remove_dir_all(dir_path.parent().unwrap());
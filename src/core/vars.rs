@@ -0,0 +1,174 @@
+//! Parsing `NAME=VALUE` lists, shared by the CLI's `--from` flag and snapshot
+//! `from.txt` files so both accept the same quoting and escapes.
+
+use crate::{Error, Result};
+
+/// Parses `NAME=VALUE` pairs separated by commas or newlines, as given to
+/// `--from` or written in a snapshot's `from.txt`.
+///
+/// Whitespace around names and values is trimmed. Single or double quotes around
+/// a value keep commas, newlines, `=` and surrounding spaces in it. Outside single
+/// quotes a backslash escapes `,`, `=`, quotes and itself; other backslashes are
+/// literal.
+///
+/// ```rust
+/// let pairs = spark::vars::parse_from(r#"name=spark, motto="fast, small", eq=a\=b"#)?;
+///
+/// assert_eq!(pairs[1], ("motto".to_string(), "fast, small".to_string()));
+/// assert_eq!(pairs[2], ("eq".to_string(), "a=b".to_string()));
+/// # Ok::<(), spark::Error>(())
+/// ```
+pub fn parse_from(input: &str) -> Result<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    for item in split_items(input)? {
+        if item
+            .iter()
+            .all(|(c, literal)| !literal && c.is_whitespace())
+        {
+            continue;
+        }
+        let Some(eq) = item.iter().position(|&(c, literal)| c == '=' && !literal) else {
+            return Err(Error::Custom(format!(
+                "expected NAME=VALUE, got '{}'",
+                text(&item)
+            )));
+        };
+        let name = trimmed(&item[..eq]);
+        if name.is_empty() {
+            return Err(Error::Custom(format!(
+                "missing variable name in '{}'",
+                text(&item)
+            )));
+        }
+        pairs.push((name, trimmed(&item[eq + 1..])));
+    }
+    Ok(pairs)
+}
+
+/// Characters a backslash escapes; before anything else it is kept as is.
+const ESCAPABLE: &[char] = &[',', '=', '\\', '\'', '"'];
+
+/// Splits on unquoted, unescaped commas and newlines. Each character is paired with whether
+/// it was quoted or escaped, which makes it exempt from splitting and trimming.
+/// Quotes only open at the start of a name or value, so `it's` needs no escaping.
+fn split_items(input: &str) -> Result<Vec<Vec<(char, bool)>>> {
+    let mut items = vec![Vec::new()];
+    let mut quote = None;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let item = items.last_mut().expect("items is never empty");
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), c) => item.push((c, true)),
+            (_, '\\') => match chars.next_if(|next| ESCAPABLE.contains(next)) {
+                Some(next) => item.push((next, true)),
+                None => item.push((c, true)),
+            },
+            (Some(_), c) => item.push((c, true)),
+            (None, '\'' | '"') if at_part_start(item) => quote = Some(c),
+            (None, ',' | '\n') => items.push(Vec::new()),
+            (None, c) => item.push((c, false)),
+        }
+    }
+
+    match quote {
+        Some(q) => Err(Error::Custom(format!("unterminated {} quote", q))),
+        None => Ok(items),
+    }
+}
+
+/// Whether nothing but blanks has been read of the name or value being parsed.
+fn at_part_start(item: &[(char, bool)]) -> bool {
+    let part = match item.iter().position(|&(c, literal)| c == '=' && !literal) {
+        Some(eq) => &item[eq + 1..],
+        None => item,
+    };
+    part.iter()
+        .all(|&(c, literal)| !literal && c.is_whitespace())
+}
+
+fn trimmed(chars: &[(char, bool)]) -> String {
+    let blank = |&(c, literal): &(char, bool)| !literal && c.is_whitespace();
+    let start = chars.iter().position(|c| !blank(c)).unwrap_or(chars.len());
+    let end = chars
+        .iter()
+        .rposition(|c| !blank(c))
+        .map_or(start, |i| i + 1);
+    chars[start..end].iter().map(|&(c, _)| c).collect()
+}
+
+fn text(chars: &[(char, bool)]) -> String {
+    chars
+        .iter()
+        .map(|&(c, _)| c)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(input: &str) -> Vec<(String, String)> {
+        parse_from(input).unwrap()
+    }
+
+    #[test]
+    fn from_splits_on_commas_and_trims() {
+        assert_eq!(
+            pairs("name=spark, author = me ,"),
+            [
+                ("name".to_string(), "spark".to_string()),
+                ("author".to_string(), "me".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn from_supports_quotes_and_escapes() {
+        assert_eq!(
+            pairs(
+                r#"motto="fast, small", eq=a\=b\,c, raw='a "b" \n', pad=" x ", quip=it's, path=C:\dir"#
+            ),
+            [
+                ("motto".to_string(), "fast, small".to_string()),
+                ("eq".to_string(), "a=b,c".to_string()),
+                ("raw".to_string(), r#"a "b" \n"#.to_string()),
+                ("pad".to_string(), " x ".to_string()),
+                ("quip".to_string(), "it's".to_string()),
+                ("path".to_string(), r"C:\dir".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_reports_malformed_input() {
+        assert_eq!(
+            parse_from("name=spark, oops").unwrap_err().to_string(),
+            "expected NAME=VALUE, got 'oops'"
+        );
+        assert!(parse_from("=value")
+            .unwrap_err()
+            .to_string()
+            .contains("missing variable name"));
+        assert!(parse_from("a=\"open")
+            .unwrap_err()
+            .to_string()
+            .contains("unterminated"));
+    }
+
+    #[test]
+    fn from_splits_on_newlines_outside_quotes() {
+        assert_eq!(
+            pairs("A=1\r\nB = 2\n\nC=\"x\ny\", D=4\n"),
+            [
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "2".to_string()),
+                ("C".to_string(), "x\ny".to_string()),
+                ("D".to_string(), "4".to_string()),
+            ]
+        );
+    }
+}